@@ -6,34 +6,67 @@ use crate::parser::{self, ParseState};
 pub struct OutputEvent {
     pub collapse_key: Option<String>,
     pub rendered_lines: Vec<String>,
+    pub parsed: Option<ParsedLine>,
 }
 
-pub fn process_raw_line(
-    config: &Config,
-    line: &str,
-    state: &mut ParseState,
-) -> Option<OutputEvent> {
-    let parsed = parser::parse_line(config, line, state)?;
+pub fn process_raw_line(config: &Config, line: &str, state: &mut ParseState) -> Vec<OutputEvent> {
+    let parsed = parser::parse_lines(config, line, state);
 
-    if matches!(
-        parsed,
-        crate::model::ParsedLine::Structured(_) | crate::model::ParsedLine::Raw(_)
-    ) {
+    if parsed.iter().any(|parsed| {
+        matches!(
+            parsed,
+            crate::model::ParsedLine::Structured(_) | crate::model::ParsedLine::Raw(..)
+        )
+    }) {
         crate::pipeline::maybe_run_action(config, line);
     }
 
-    let processed = crate::pipeline::process_line(config, parsed)?;
+    process_parsed(config, line, parsed, state)
+}
+
+/// The events of the records the parser still holds at the end of the input.
+pub fn finish_input(config: &Config, state: &mut ParseState) -> Vec<OutputEvent> {
+    let parsed = parser::finish(config, state);
+    process_parsed(config, "", parsed, state)
+}
+
+fn process_parsed(
+    config: &Config,
+    line: &str,
+    parsed: Vec<ParsedLine>,
+    state: &mut ParseState,
+) -> Vec<OutputEvent> {
+    parsed
+        .into_iter()
+        .filter_map(|parsed| {
+            let processed = crate::pipeline::process_line(config, parsed)?;
+            let followed =
+                crate::pipeline::follow_field(config, &processed, line, &mut state.followed_values);
+            let filter = config
+                .follow_field
+                .as_ref()
+                .is_some_and(|follow| follow.filter);
+            if filter && followed == Some(false) {
+                return None;
+            }
 
-    Some(OutputEvent {
-        collapse_key: collapse_key(&processed),
-        rendered_lines: crate::render::render_parsed_line(config, &processed, state),
-    })
+            let mut rendered_lines = crate::render::render_parsed_line(config, &processed, state);
+            if let (Some(marked), false) = (followed, filter) {
+                rendered_lines = crate::render::mark_lines(config, rendered_lines, marked);
+            }
+            Some(OutputEvent {
+                collapse_key: collapse_key(&processed),
+                rendered_lines,
+                parsed: Some(processed),
+            })
+        })
+        .collect()
 }
 
 fn collapse_key(parsed: &ParsedLine) -> Option<String> {
     match parsed {
         ParsedLine::Structured(log) => Some(structured_log_key(log)),
-        ParsedLine::Raw(line) => Some(format!("raw\0{line}")),
+        ParsedLine::Raw(line, _) => Some(format!("raw\0{line}")),
         ParsedLine::KubectlHeader => None,
         ParsedLine::KubectlEvent(event) => Some(kubectl_event_key(event)),
     }
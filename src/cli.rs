@@ -3,6 +3,7 @@ use clap::{Command, CommandFactory, Parser, ValueHint};
 use clap_complete::{generate, Generator, Shell};
 use std::collections::HashMap;
 use std::io;
+use std::time::Duration;
 use yansi::{Color, Style};
 
 // `cstr!` converts tags to ANSI codes
@@ -27,18 +28,61 @@ Try to stream some logs or specify a log file and let snazy, <red>snazzy them</r
     about,
     after_help = AFTER_HELP)]
 struct Args {
-    #[arg(short = 'r', long, verbatim_doc_comment)]
+    #[arg(short = 'r', long, visible_alias = "highlight", verbatim_doc_comment)]
     /// regexp highlight
     ///
-    /// Highlight a pattern in a message with a regexp
+    /// Highlight a pattern in a message with a regexp, as 'color:regexp' or
+    /// 'style=/regexp/flags' where the flags are i (ignore case), l (literal)
+    /// and w (whole words), the style can be left out as '=/regexp/flags'
     pub regexp: Vec<String>,
 
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        env = "SNAZY_HIGHLIGHT_PRESET",
+        value_name = "preset"
+    )]
+    /// Builtin highlighters for common entities, styled by the theme
+    ///
+    /// The -r regexps win over the presets when they overlap.
+    pub highlight_preset: Vec<crate::config::HighlightPreset>,
+
+    #[arg(long, value_name = "style:condition")]
+    /// Style the value of an extra field matching a condition, e.g. 'magenta:namespace=prod'
+    ///
+    /// The condition compares a field (nested fields use dots) with =, !=, >, >=, <, <=
+    /// or ~ for a regexp, the style uses the --template syntax.
+    pub highlight_field: Vec<String>,
+
+    #[arg(long, value_name = "style:condition")]
+    /// Style the whole line of the records matching a condition, e.g. 'red:status>=500'
+    ///
+    /// Same syntax as --highlight-field, the first matching rule wins.
+    pub highlight_line: Vec<String>,
+
     #[arg(short = 'S', long)]
     /// Skip a line matching a Regexp.
     ///
-    /// Any lines matching the regexp, will be skipped to be printed.
+    /// Any lines matching the regexp, will be skipped to be printed. It can be
+    /// written as =/regexp/flags like the -r regexps.
     pub skip_line_regexp: Vec<String>,
 
+    #[arg(long = "match", value_name = "regexp")]
+    /// Only show the lines matching a regexp, it can be given many times
+    ///
+    /// The regexps are matched against the message of the structured logs and
+    /// the whole raw lines, they can be written as =/regexp/flags like -r.
+    pub match_regexps: Vec<String>,
+
+    #[arg(long, value_enum, default_value_t = crate::config::MatchMode::Any)]
+    /// Whether a line has to match any or all of the --match regexps
+    pub match_mode: crate::config::MatchMode,
+
+    #[arg(long, action(clap::ArgAction::SetTrue))]
+    /// Match the --match regexps against the key=value fields of the structured logs too
+    pub match_fields: bool,
+
     /// If provided, outputs the completion file for given shell
     #[arg(long, value_enum)]
     shell_completion: Option<Shell>,
@@ -116,6 +160,11 @@ struct Args {
     #[arg(long, action(clap::ArgAction::SetTrue), env = "SNAZY_LEVEL_SYMBOLS")]
     pub level_symbols: bool,
 
+    /// Symbols shown with --level-symbols: emoji, nerd, ascii or a set of the config file,
+    /// implies --level-symbols
+    #[arg(long, env = "SNAZY_SYMBOL_SET", value_name = "name")]
+    pub symbol_set: Option<String>,
+
     #[arg(short = 'k', long, verbatim_doc_comment)]
     /// Keys / Values for JSON Parsing
     ///
@@ -136,6 +185,7 @@ struct Args {
     ///  You can have an action matching a Regexp.
     ///  A good example is when  you have to have a notification on your desktop
     ///  when there is a match in a log.
+    ///  It can be written as =/regexp/flags like the -r regexps.
     pub action_regexp: Option<String>,
 
     #[arg(long, verbatim_doc_comment)]
@@ -154,54 +204,194 @@ struct Args {
     /// Include specific fields from JSON logs (comma-separated)
     pub include_fields: Vec<String>,
 
+    #[arg(long = "source", value_name = "glob", verbatim_doc_comment)]
+    /// Only show lines coming from a matching kail source
+    ///
+    /// The glob is matched against `namespace/pod[container]`, `*` matches any
+    /// characters and `?` a single one. You can have multiple sources, e.g:
+    /// `--source '*/controller-*[manager]'`
+    pub source: Vec<String>,
+
+    #[arg(long, value_name = "glob")]
+    /// Hide lines coming from a matching kail source (same syntax as --source)
+    pub exclude_source: Vec<String>,
+
+    #[arg(long, value_name = "directory", value_hint = ValueHint::DirPath, verbatim_doc_comment)]
+    /// Also write each source to its own file in a directory
+    ///
+    /// Every kail pod/container (or every input file when the line has no kail
+    /// prefix) gets its own file, the combined view is still printed.
+    pub split_output: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = crate::config::SplitFormat::Rendered)]
+    /// Format of the files written by --split-output
+    pub split_format: crate::config::SplitFormat,
+
+    #[arg(
+        long,
+        default_value = "500ms",
+        value_parser = crate::utils::parse_duration,
+        value_name = "duration",
+        env = "SNAZY_LATENCY_WARNING"
+    )]
+    /// Highlight access log latencies above this duration as a warning
+    pub latency_warning: Duration,
+
+    #[arg(
+        long,
+        default_value = "2s",
+        value_parser = crate::utils::parse_duration,
+        value_name = "duration",
+        env = "SNAZY_LATENCY_CRITICAL"
+    )]
+    /// Highlight access log latencies above this duration as critical
+    pub latency_critical: Duration,
+
+    #[arg(long, value_parser = crate::utils::parse_duration, value_name = "duration")]
+    /// Only show access log requests slower than this duration (e.g. 500ms)
+    pub slow: Option<Duration>,
+
+    #[arg(long, value_name = "field", env = "SNAZY_GROUP_BY")]
+    /// Group the records sharing the same field value (e.g. `trace_id`), nested fields use dots
+    pub group_by: Option<String>,
+
+    #[arg(
+        long,
+        value_parser = crate::utils::parse_duration,
+        value_name = "duration",
+        requires = "group_by"
+    )]
+    /// Print a group once it has not received any record for this duration, instead of at the end
+    pub group_idle: Option<Duration>,
+
+    #[arg(long, value_name = "field[=value]", conflicts_with = "mark_field")]
+    /// Only show the records with the same field value, learned from the first trigger line
+    /// when no value is given
+    pub follow_field: Option<String>,
+
+    #[arg(long, value_name = "field[=value]")]
+    /// Like --follow-field but mark the matching records instead of hiding the others
+    pub mark_field: Option<String>,
+
+    #[arg(long, value_name = "regexp")]
+    /// Learn the followed field value from the lines matching this regexp (default: errors)
+    pub follow_trigger: Option<String>,
+
+    #[arg(long, action(clap::ArgAction::SetTrue), env = "SNAZY_EXPAND_FIELDS")]
+    /// Show the extra fields below the message as an indented tree
+    pub expand_fields: bool,
+
+    #[arg(long, default_value_t = 4, value_name = "depth")]
+    /// Nesting depth after which --expand-fields shows objects as one line JSON
+    pub expand_depth: usize,
+
+    #[arg(long, default_value_t = 10, value_name = "count")]
+    /// Number of array items shown by --expand-fields before truncating
+    pub expand_array_limit: usize,
+
+    #[arg(
+        long,
+        value_name = "template",
+        env = "SNAZY_TEMPLATE",
+        verbatim_doc_comment
+    )]
+    /// Layout of the structured log lines, e.g:
+    ///
+    /// '{time} {level:5} {?[{field:component|cyan}] ?}{msg} {extras}'
+    ///
+    /// Placeholders are time, level, delta, prefix, others, msg, extras and
+    /// field:<path>. They take an optional :[<>]width.max format and a |style
+    /// (colors, bold, dim, italic, underline, bg=color). The segments between
+    /// {? and ?} are only shown when one of their placeholders has a value.
+    pub template: Option<String>,
+
+    #[arg(long, value_enum, default_value = "text", env = "SNAZY_OUTPUT")]
+    /// Output format of the records
+    pub output: crate::config::OutputFormat,
+
+    #[arg(long, value_delimiter = ',', value_name = "column[:width]")]
+    /// Columns of the table, csv and tsv outputs: ts, level, msg, others, prefix, source,
+    /// stacktrace or a JSON field path (default: ts,level,msg)
+    pub columns: Vec<String>,
+
+    #[arg(long, env = "COLUMNS", value_name = "columns")]
+    /// Width used to fit the table output (default: the width of the terminal or 120)
+    pub width: Option<usize>,
+
+    #[arg(long, default_value = "dark", env = "SNAZY_THEME", value_name = "name")]
+    /// Color theme: dark, light, solarized, high-contrast, mono or a theme of the config file
+    pub theme: String,
+
+    #[arg(long, env = "SNAZY_CONFIG", value_name = "file", value_hint = ValueHint::FilePath)]
+    /// JSON config file defining themes (default: `$XDG_CONFIG_HOME/snazy/config.json`)
+    pub config: Option<String>,
+
     #[arg(value_hint = ValueHint::FilePath)]
     files: Option<Vec<String>>,
 }
 
-fn regexp_colorize(regexps: &[String]) -> HashMap<String, Style> {
-    let mut regexp_colours = HashMap::new();
-    let colours = [
-        Color::Cyan,
-        Color::Yellow,
-        Color::Red,
-        Color::Magenta,
-        Color::Blue,
-    ];
-    for (i, regexp) in regexps.iter().enumerate() {
-        let defchosen = colours[i % colours.len()];
-        let mut foreground = defchosen;
-        let mut background = None;
-        let mut reg = regexp.clone();
-        if let Some(colour) = regexp.split(':').next() {
-            if colour.contains("bg=") && colour.contains("fg=") && colour.split(',').count() == 2 {
-                let parts: Vec<&str> = colour.split(',').collect();
-                for part in parts {
-                    if let Some(colorsss) = part.strip_prefix("bg=") {
-                        background = Some(parse_color(colorsss));
-                    } else if let Some(colorsss) = part.strip_prefix("fg=") {
-                        foreground = parse_color(colorsss);
-                    }
-                }
-            } else if colour.split(',').count() == 3 {
-                let mut parts = colour.split(',');
-                let r = parts.next().unwrap().parse::<u8>().unwrap();
-                let g = parts.next().unwrap().parse::<u8>().unwrap();
-                let b = parts.next().unwrap().parse::<u8>().unwrap();
-                foreground = Color::Rgb(r, g, b);
-            } else if let Ok(col) = colour.parse::<u8>() {
-                foreground = Color::Fixed(col);
-            } else {
-                foreground = match_color(colour, defchosen);
-            }
-            reg = regexp.replace(format!("{colour}:").as_str(), "");
+/// The `-r` regexps with their style, in the order they were given. A
+/// pattern is either `style=/regexp/flags` (the style can be left out) or the
+/// older `color:regexp` where the prefix is only taken when it is a color.
+fn regexp_colorize(regexps: &[String], palette: &[Style]) -> Vec<(String, Style)> {
+    regexps
+        .iter()
+        .enumerate()
+        .map(|(i, regexp)| {
+            let (style, pattern) = highlight_style(regexp);
+            (
+                pattern.to_string(),
+                style.unwrap_or(palette[i % palette.len()]),
+            )
+        })
+        .collect()
+}
+
+fn highlight_style(regexp: &str) -> (Option<Style>, &str) {
+    if let Some((style, _)) = regexp.split_once("=/") {
+        if style.is_empty() {
+            return (None, regexp);
         }
-        let mut style = Style::new().fg(foreground);
-        if let Some(bg) = background {
-            style = style.bg(bg);
+        if let Ok(parsed) = crate::template::parse_style(style) {
+            return (Some(parsed), &regexp[style.len()..]);
         }
-        regexp_colours.insert(reg, style);
     }
-    regexp_colours
+    let colour = regexp.split(':').next().unwrap_or_default();
+    match legacy_color_style(colour) {
+        Some(style) => (
+            Some(style),
+            regexp
+                .strip_prefix(colour)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .unwrap_or(regexp),
+        ),
+        None => (None, regexp),
+    }
+}
+
+/// The `color:` prefix of a `-r` regexp: a color name, an `r,g,b` or a fixed
+/// color, or `fg=color,bg=color`.
+fn legacy_color_style(colour: &str) -> Option<Style> {
+    if colour.contains("bg=") && colour.contains("fg=") && colour.split(',').count() == 2 {
+        let part = |prefix: &str| {
+            colour
+                .split(',')
+                .find_map(|part| part.strip_prefix(prefix))
+                .map(parse_color)
+        };
+        return Some(Style::new().fg(part("fg=")?).bg(part("bg=")?));
+    }
+    let rgb: Option<Vec<u8>> = colour.split(',').map(|part| part.parse().ok()).collect();
+    if let Some([r, g, b]) = rgb.as_deref() {
+        return Some(Style::new().fg(Color::Rgb(*r, *g, *b)));
+    }
+    if let Ok(col) = colour.parse::<u8>() {
+        return Some(Style::new().fg(Color::Fixed(col)));
+    }
+    match match_color(colour, Color::Primary) {
+        Color::Primary => None,
+        named => Some(Style::new().fg(named)),
+    }
 }
 
 fn parse_color(color: &str) -> Color {
@@ -218,7 +408,7 @@ fn parse_color(color: &str) -> Color {
     }
 }
 
-fn match_color(color: &str, default: Color) -> Color {
+pub(crate) fn match_color(color: &str, default: Color) -> Color {
     match color.to_lowercase().as_str() {
         "yellow" => Color::Yellow,
         "cyan" => Color::Cyan,
@@ -247,6 +437,17 @@ fn make_json_keys(json_keys: &[String]) -> HashMap<String, String> {
     ret
 }
 
+fn field_follow(selector: &str, filter: bool) -> crate::config::FieldFollow {
+    let (field, value) = selector
+        .split_once('=')
+        .map_or((selector, None), |(field, value)| (field, Some(value)));
+    crate::config::FieldFollow {
+        field: field.to_string(),
+        value: value.map(ToString::to_string),
+        filter,
+    }
+}
+
 fn print_completions<G: Generator>(gen: G, cmd: &mut Command) {
     generate(gen, cmd, cmd.get_name().to_string(), &mut io::stdout());
 }
@@ -265,16 +466,63 @@ pub fn build_cli_config() -> Config {
         std::process::exit(1);
     }
 
-    let regexp_colours = regexp_colorize(&args.regexp);
+    let config_file = crate::config_file::load(args.config.as_deref()).unwrap_or_else(|error| {
+        eprintln!("invalid config file {error}");
+        std::process::exit(1);
+    });
+    let theme =
+        crate::theme::Theme::load(&args.theme, &config_file.themes).unwrap_or_else(|error| {
+            eprintln!("invalid --theme: {error}");
+            std::process::exit(1);
+        });
+    let symbol_set = crate::levels::SymbolSet::load(
+        args.symbol_set.as_deref().unwrap_or("emoji"),
+        &config_file.symbol_sets,
+    )
+    .unwrap_or_else(|error| {
+        eprintln!("invalid --symbol-set: {error}");
+        std::process::exit(1);
+    });
+    let mut regexp_colours =
+        crate::highlight::Highlighter::compile(&regexp_colorize(&args.regexp, &theme.highlights))
+            .unwrap_or_else(|error| {
+                eprintln!("invalid --regexp: {error}");
+                std::process::exit(1);
+            });
+    regexp_colours.add_presets(&args.highlight_preset, &theme);
+    let compile_pattern = |pattern: &str, flag: &str| {
+        crate::pattern::compile(pattern).unwrap_or_else(|error| {
+            eprintln!("invalid --{flag}: {error}");
+            std::process::exit(1);
+        })
+    };
+    let field_highlights =
+        |rules: &[String], flag: &str| -> Vec<crate::highlight::FieldHighlight> {
+            rules
+                .iter()
+                .map(|rule| {
+                    crate::highlight::FieldHighlight::parse(rule).unwrap_or_else(|error| {
+                        eprintln!("invalid --{flag}: {error}");
+                        std::process::exit(1);
+                    })
+                })
+                .collect()
+        };
     let coloring = args.color;
     if coloring == crate::config::Coloring::Never {
         yansi::disable();
     }
     let json_keys = make_json_keys(&args.json_keys);
+    let template = args.template.as_deref().map(|template| {
+        crate::template::Template::parse(template).unwrap_or_else(|error| {
+            eprintln!("invalid --template: {error}");
+            std::process::exit(1);
+        })
+    });
 
     Config {
-        level_symbols: if args.level_symbols {
-            crate::config::LevelSymbols::Emoji
+        level_symbols: if args.level_symbols || args.symbol_set.is_some() {
+            crate::config::LevelSymbols::Symbols
         } else {
             crate::config::LevelSymbols::Text
         },
@@ -289,16 +537,72 @@ pub fn build_cli_config() -> Config {
         time_format: args.time_format,
         time_delta: args.time_delta,
         timezone: args.timezone,
-        skip_line_regexp: args.skip_line_regexp,
+        skip_line_regexp: args
+            .skip_line_regexp
+            .iter()
+            .map(|pattern| compile_pattern(pattern, "skip-line-regexp"))
+            .collect(),
+        match_regexps: args
+            .match_regexps
+            .iter()
+            .map(|pattern| compile_pattern(pattern, "match"))
+            .collect(),
+        match_mode: args.match_mode,
+        match_fields: args.match_fields,
         filter_levels: args.filter_levels,
         action_command: args.action_command,
-        action_regexp: args.action_regexp,
+        action_regexp: args
+            .action_regexp
+            .as_deref()
+            .map(|pattern| compile_pattern(pattern, "action-regexp")),
         files: args.files,
         regexp_colours,
+        field_highlights: field_highlights(&args.highlight_field, "highlight-field"),
+        line_highlights: field_highlights(&args.highlight_line, "highlight-line"),
         json_keys,
         hide_stacktrace: args.hide_stacktrace,
         coloring,
         extra_fields: args.extra_fields,
         include_fields: args.include_fields,
+        source_filters: args.source,
+        exclude_source_filters: args.exclude_source,
+        split_output: args.split_output,
+        split_format: args.split_format,
+        latency_warning: args.latency_warning,
+        latency_critical: args.latency_critical,
+        slow_requests: args.slow,
+        group_by: args.group_by,
+        group_idle: args.group_idle,
+        follow_field: args
+            .follow_field
+            .as_deref()
+            .map(|selector| field_follow(selector, true))
+            .or_else(|| {
+                args.mark_field
+                    .as_deref()
+                    .map(|selector| field_follow(selector, false))
+            }),
+        follow_trigger: args
+            .follow_trigger
+            .as_deref()
+            .map(|pattern| compile_pattern(pattern, "follow-trigger")),
+        expand_fields: args.expand_fields,
+        expand_depth: args.expand_depth,
+        expand_array_limit: args.expand_array_limit,
+        template,
+        output: args.output,
+        columns: args.columns,
+        width: args
+            .width
+            .or_else(crate::utils::terminal_width)
+            .unwrap_or(120),
+        table_layout: std::sync::OnceLock::new(),
+        theme,
+        symbol_set,
+        level_labels: config_file
+            .level_labels
+            .into_iter()
+            .map(|(level, label)| (level.to_uppercase(), label))
+            .collect(),
     }
 }
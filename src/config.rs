@@ -1,7 +1,7 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use clap::ValueEnum;
-use yansi::Style;
 
 #[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum LogLevel {
@@ -14,9 +14,9 @@ pub enum LogLevel {
 
 pub fn level_from_str(level: &str) -> &'static LogLevel {
     match level {
-        "debug" => &LogLevel::Debug,
+        "trace" | "debug" => &LogLevel::Debug,
         "warn" | "warning" => &LogLevel::Warning,
-        "err" | "error" => &LogLevel::Error,
+        "err" | "error" | "crit" | "critical" | "alert" | "emerg" => &LogLevel::Error,
         "fatal" => &LogLevel::Fatal,
         _ => &LogLevel::Info,
     }
@@ -30,7 +30,7 @@ pub enum KailPrefix {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum LevelSymbols {
-    Emoji,
+    Symbols,
     Text,
 }
 
@@ -41,11 +41,64 @@ pub enum Coloring {
     Auto,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Table,
+    Csv,
+    Tsv,
+    Html,
+}
+
+impl OutputFormat {
+    /// The CSV and TSV outputs are data, they get no summary or header lines.
+    pub fn is_delimited(self) -> bool {
+        matches!(self, Self::Csv | Self::Tsv)
+    }
+}
+
+/// The builtin `--highlight-preset` highlighters, in their priority order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HighlightPreset {
+    Url,
+    Uuid,
+    Ip,
+    Sha,
+    K8s,
+    Path,
+    Http,
+    Duration,
+    Quoted,
+    All,
+}
+
+/// Whether a line must match any or all of the `--match` regexps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MatchMode {
+    Any,
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SplitFormat {
+    Rendered,
+    Ndjson,
+}
+
+/// `--follow-field`/`--mark-field`, the value is learned from the trigger
+/// lines when it is not given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldFollow {
+    pub field: String,
+    pub value: Option<String>,
+    pub filter: bool,
+}
+
 #[derive(Debug, Clone)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     pub action_command: Option<String>,
-    pub action_regexp: Option<String>,
+    pub action_regexp: Option<regex::Regex>,
     pub collapse_duplicates: bool,
     pub collapse_window_seconds: u64,
     pub files: Option<Vec<String>>,
@@ -54,8 +107,13 @@ pub struct Config {
     pub kail_prefix_format: String,
     pub kail_prefix: KailPrefix,
     pub level_symbols: LevelSymbols,
-    pub regexp_colours: HashMap<String, Style>,
-    pub skip_line_regexp: Vec<String>,
+    pub regexp_colours: crate::highlight::Highlighter,
+    pub field_highlights: Vec<crate::highlight::FieldHighlight>,
+    pub line_highlights: Vec<crate::highlight::FieldHighlight>,
+    pub skip_line_regexp: Vec<regex::Regex>,
+    pub match_regexps: Vec<regex::Regex>,
+    pub match_mode: MatchMode,
+    pub match_fields: bool,
     pub time_format: String,
     pub time_delta: bool,
     pub timezone: Option<String>,
@@ -63,6 +121,28 @@ pub struct Config {
     pub coloring: Coloring,
     pub extra_fields: bool,
     pub include_fields: Vec<String>,
+    pub source_filters: Vec<String>,
+    pub exclude_source_filters: Vec<String>,
+    pub split_output: Option<String>,
+    pub split_format: SplitFormat,
+    pub latency_warning: Duration,
+    pub latency_critical: Duration,
+    pub slow_requests: Option<Duration>,
+    pub group_by: Option<String>,
+    pub group_idle: Option<Duration>,
+    pub follow_field: Option<FieldFollow>,
+    pub follow_trigger: Option<regex::Regex>,
+    pub expand_fields: bool,
+    pub expand_depth: usize,
+    pub expand_array_limit: usize,
+    pub template: Option<crate::template::Template>,
+    pub output: OutputFormat,
+    pub columns: Vec<String>,
+    pub width: usize,
+    pub table_layout: std::sync::OnceLock<crate::output::TableLayout>,
+    pub theme: crate::theme::Theme,
+    pub symbol_set: crate::levels::SymbolSet,
+    pub level_labels: HashMap<String, crate::levels::LevelLabel>,
 }
 
 impl Default for Config {
@@ -77,16 +157,43 @@ impl Default for Config {
             time_delta: false,
             timezone: None,
             filter_levels: <Vec<LogLevel>>::new(),
-            regexp_colours: HashMap::new(),
+            regexp_colours: crate::highlight::Highlighter::default(),
+            field_highlights: Vec::new(),
+            line_highlights: Vec::new(),
             json_keys: HashMap::new(),
             level_symbols: LevelSymbols::Text,
-            action_regexp: Some(String::new()),
+            action_regexp: None,
             action_command: Some(String::new()),
             skip_line_regexp: Vec::new(),
+            match_regexps: Vec::new(),
+            match_mode: MatchMode::Any,
+            match_fields: false,
             hide_stacktrace: false,
             coloring: Coloring::Auto,
             extra_fields: false,
             include_fields: Vec::new(),
+            source_filters: Vec::new(),
+            exclude_source_filters: Vec::new(),
+            split_output: None,
+            split_format: SplitFormat::Rendered,
+            latency_warning: Duration::from_millis(500),
+            latency_critical: Duration::from_secs(2),
+            slow_requests: None,
+            group_by: None,
+            group_idle: None,
+            follow_field: None,
+            follow_trigger: None,
+            expand_fields: false,
+            expand_depth: 4,
+            expand_array_limit: 10,
+            template: None,
+            output: OutputFormat::Text,
+            columns: Vec::new(),
+            width: 120,
+            table_layout: std::sync::OnceLock::new(),
+            theme: crate::theme::Theme::default(),
+            symbol_set: crate::levels::SymbolSet::default(),
+            level_labels: HashMap::new(),
         }
     }
 }
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::levels::{LevelLabel, SymbolSetDefinition};
+use crate::theme::ThemeDefinition;
+
+/// The JSON config file, `$XDG_CONFIG_HOME/snazy/config.json` unless
+/// `--config` points to another one.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub themes: HashMap<String, ThemeDefinition>,
+    #[serde(default)]
+    pub symbol_sets: HashMap<String, SymbolSetDefinition>,
+    #[serde(default)]
+    pub level_labels: HashMap<String, LevelLabel>,
+}
+
+fn default_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|directory| !directory.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|directory| directory.join("snazy").join("config.json"))
+}
+
+/// Load the config file, a missing default file is not an error.
+pub fn load(path: Option<&str>) -> Result<ConfigFile, String> {
+    let (path, explicit) = match path {
+        Some(path) => (PathBuf::from(path), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(ConfigFile::default()),
+        },
+    };
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(error) if !explicit && error.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(ConfigFile::default());
+        }
+        Err(error) => return Err(format!("{}: {error}", path.display())),
+    };
+    serde_json::from_str(&content).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    #[test]
+    fn loads_explicit_config_files() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(br#"{"themes": {"paper": {"inherits": "light", "timestamp": "blue"}}}"#)
+            .unwrap();
+        let config = super::load(file.path().to_str()).unwrap();
+        assert_eq!(config.themes["paper"].inherits.as_deref(), Some("light"));
+
+        assert!(super::load(Some("/nonexistent/snazy.json")).is_err());
+        let mut invalid = tempfile::NamedTempFile::new().unwrap();
+        invalid.write_all(br#"{"themez": {}}"#).unwrap();
+        assert!(super::load(invalid.path().to_str()).is_err());
+    }
+}
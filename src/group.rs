@@ -0,0 +1,233 @@
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
+
+use crate::app::OutputEvent;
+use crate::config::Config;
+use crate::model::ParsedLine;
+
+/// Buffer the records sharing the same `--group-by` field value and print
+/// them together behind a header, at the end of the input or once a group
+/// has been idle for `--group-idle`.
+pub struct Grouper<'a> {
+    config: &'a Config,
+    groups: Vec<Group>,
+}
+
+pub struct Group {
+    pub key: String,
+    pub events: Vec<OutputEvent>,
+    pub first_timestamp: Option<DateTime<Utc>>,
+    pub last_timestamp: Option<DateTime<Utc>>,
+    pub worst_level: Option<String>,
+    last_seen: Instant,
+}
+
+impl<'a> Grouper<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        Self {
+            config,
+            groups: Vec::new(),
+        }
+    }
+
+    /// Returns the events ready to be printed, the records without the group
+    /// field are passed through as they come.
+    pub fn push(&mut self, event: OutputEvent, now: Instant) -> Vec<OutputEvent> {
+        let Some(field) = self.config.group_by.as_deref() else {
+            return vec![event];
+        };
+
+        let mut ready = self.flush_idle(now);
+        let Some(key) = group_key(field, event.parsed.as_ref()) else {
+            ready.push(event);
+            return ready;
+        };
+
+        let index = self
+            .groups
+            .iter()
+            .position(|group| group.key == key)
+            .unwrap_or_else(|| {
+                self.groups.push(Group {
+                    key,
+                    events: Vec::new(),
+                    first_timestamp: None,
+                    last_timestamp: None,
+                    worst_level: None,
+                    last_seen: now,
+                });
+                self.groups.len() - 1
+            });
+        let group = &mut self.groups[index];
+        group.last_seen = now;
+        if let Some(ParsedLine::Structured(log)) = event.parsed.as_ref() {
+            if let Some(timestamp) = log.parsed_timestamp {
+                group.first_timestamp = Some(
+                    group
+                        .first_timestamp
+                        .map_or(timestamp, |first| first.min(timestamp)),
+                );
+                group.last_timestamp = Some(
+                    group
+                        .last_timestamp
+                        .map_or(timestamp, |last| last.max(timestamp)),
+                );
+            }
+            if group
+                .worst_level
+                .as_deref()
+                .is_none_or(|worst| level_rank(&log.level) > level_rank(worst))
+            {
+                group.worst_level = Some(log.level.clone());
+            }
+        }
+        group.events.push(event);
+        ready
+    }
+
+    pub fn finish(&mut self) -> Vec<OutputEvent> {
+        let groups = std::mem::take(&mut self.groups);
+        groups
+            .into_iter()
+            .flat_map(|group| self.render_group(group))
+            .collect()
+    }
+
+    /// When the next group goes idle, to flush it even if no line comes.
+    pub fn next_idle_deadline(&self) -> Option<Instant> {
+        let idle = self.config.group_idle?;
+        self.groups.iter().map(|group| group.last_seen + idle).min()
+    }
+
+    pub fn flush_idle(&mut self, now: Instant) -> Vec<OutputEvent> {
+        let Some(idle) = self.config.group_idle else {
+            return Vec::new();
+        };
+
+        let (idle_groups, active): (Vec<Group>, Vec<Group>) = std::mem::take(&mut self.groups)
+            .into_iter()
+            .partition(|group| now.duration_since(group.last_seen) >= idle);
+        self.groups = active;
+        idle_groups
+            .into_iter()
+            .flat_map(|group| self.render_group(group))
+            .collect()
+    }
+
+    fn render_group(&self, group: Group) -> Vec<OutputEvent> {
+        let header = crate::render::render_group_header(
+            self.config,
+            self.config.group_by.as_deref().unwrap_or_default(),
+            &group,
+        );
+        let mut events: Vec<OutputEvent> = header
+            .map(|header| OutputEvent {
+                collapse_key: None,
+                rendered_lines: vec![header],
+                parsed: None,
+            })
+            .into_iter()
+            .collect();
+        events.extend(group.events);
+        events
+    }
+}
+
+fn group_key(field: &str, parsed: Option<&ParsedLine>) -> Option<String> {
+    let Some(ParsedLine::Structured(log)) = parsed else {
+        return None;
+    };
+    match crate::pipeline::get_nested_value(log.raw_json.as_ref()?, field)? {
+        serde_json::Value::String(value) => Some(value.clone()),
+        serde_json::Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+fn level_rank(level: &str) -> u8 {
+    match level {
+        "TRACE" => 1,
+        "DEBUG" => 2,
+        "INFO" | "NOTICE" => 3,
+        "WARNING" => 4,
+        "ERROR" => 5,
+        "CRITICAL" => 6,
+        "FATAL" => 7,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::app::OutputEvent;
+    use crate::config::{Coloring, Config};
+    use crate::parser::ParseState;
+
+    fn event(line: &str) -> OutputEvent {
+        let config = Config {
+            coloring: Coloring::Never,
+            ..Config::default()
+        };
+        crate::app::process_raw_line(&config, line, &mut ParseState::default()).remove(0)
+    }
+
+    fn lines(events: &[OutputEvent]) -> Vec<String> {
+        events
+            .iter()
+            .flat_map(|event| event.rendered_lines.clone())
+            .collect()
+    }
+
+    #[test]
+    fn groups_records_by_field_until_finish() {
+        let config = Config {
+            group_by: Some("trace_id".to_string()),
+            coloring: Coloring::Never,
+            ..Config::default()
+        };
+        let mut grouper = super::Grouper::new(&config);
+        let now = Instant::now();
+
+        for line in [
+            r#"{"level":"info","msg":"a1","time":"2022-04-25T14:20:32Z","trace_id":"a"}"#,
+            r#"{"level":"info","msg":"b1","time":"2022-04-25T14:20:33Z","trace_id":"b"}"#,
+            r#"{"level":"error","msg":"a2","time":"2022-04-25T14:20:35Z","trace_id":"a"}"#,
+        ] {
+            assert!(grouper.push(event(line), now).is_empty());
+        }
+        let passthrough = grouper.push(event("plain line"), now);
+        assert_eq!(lines(&passthrough), vec!["plain line".to_string()]);
+
+        let rendered = lines(&grouper.finish());
+        assert_eq!(rendered.len(), 5);
+        assert_eq!(
+            rendered[0],
+            "── trace_id=a ── 14:20:32 → 14:20:35 (3s) · 2 lines · ERROR"
+        );
+        assert!(rendered[1].ends_with("a1"));
+        assert!(rendered[2].ends_with("a2"));
+        assert_eq!(rendered[3], "── trace_id=b ── 14:20:33 · 1 line · INFO");
+    }
+
+    #[test]
+    fn flushes_idle_groups() {
+        let config = Config {
+            group_by: Some("trace_id".to_string()),
+            group_idle: Some(Duration::from_secs(2)),
+            coloring: Coloring::Never,
+            ..Config::default()
+        };
+        let mut grouper = super::Grouper::new(&config);
+        let start = Instant::now();
+
+        let line = r#"{"level":"info","msg":"a1","time":"2022-04-25T14:20:32Z","trace_id":"a"}"#;
+        assert!(grouper.push(event(line), start).is_empty());
+        let line = r#"{"level":"info","msg":"b1","time":"2022-04-25T14:20:33Z","trace_id":"b"}"#;
+        let flushed = grouper.push(event(line), start + Duration::from_secs(3));
+        assert_eq!(lines(&flushed).len(), 2);
+        assert_eq!(lines(&grouper.finish()).len(), 2);
+    }
+}
@@ -0,0 +1,470 @@
+use regex::Regex;
+use yansi::{Paint, Style};
+
+use crate::config::HighlightPreset;
+use crate::utils::ANSI_RE;
+
+/// A compiled highlighting pattern and its style, when the pattern has a
+/// group named `hl` only that group is painted.
+#[derive(Debug, Clone)]
+pub struct Highlight {
+    regex: Regex,
+    style: Style,
+    grouped: bool,
+    accept: fn(&str) -> bool,
+}
+
+impl Highlight {
+    fn new(regex: Regex, style: Style) -> Self {
+        Self {
+            grouped: regex.capture_names().any(|name| name == Some("hl")),
+            regex,
+            style,
+            accept: |_| true,
+        }
+    }
+
+    fn matches<'a>(&'a self, plain: &'a str) -> Box<dyn Iterator<Item = regex::Match<'a>> + 'a> {
+        let accept = self.accept;
+        if self.grouped {
+            Box::new(
+                self.regex
+                    .captures_iter(plain)
+                    .filter_map(|captures| captures.name("hl"))
+                    .filter(move |found| accept(found.as_str())),
+            )
+        } else {
+            Box::new(
+                self.regex
+                    .find_iter(plain)
+                    .filter(move |found| accept(found.as_str())),
+            )
+        }
+    }
+}
+
+/// The patterns of a preset, the `accept` check filters out the matches a
+/// regexp can not tell apart.
+fn preset_patterns(preset: HighlightPreset) -> (&'static [&'static str], fn(&str) -> bool) {
+    match preset {
+        HighlightPreset::Url => (&[r#"\b[a-zA-Z][a-zA-Z0-9+.-]*://[^\s"'<>]+"#], |_| true),
+        HighlightPreset::Uuid => (
+            &[r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b"],
+            |_| true,
+        ),
+        HighlightPreset::Ip => (
+            &[
+                r"\b(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)(?::\d{1,5})?\b",
+                r"\b(?:[0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}\b",
+                r"\b(?:[0-9a-fA-F]{1,4}:){1,6}(?::[0-9a-fA-F]{1,4}){1,6}\b",
+                r"(?:^|[^\w:])(?P<hl>(?:[0-9a-fA-F]{1,4}:){1,7}:|::(?:[0-9a-fA-F]{1,4}:){0,6}[0-9a-fA-F]{1,4})(?:$|[^\w:])",
+            ],
+            |_| true,
+        ),
+        HighlightPreset::Sha => (&[r"\b[0-9a-f]{7,64}\b"], |text| {
+            text.bytes().any(|byte| byte.is_ascii_digit())
+                && text.bytes().any(|byte| byte.is_ascii_alphabetic())
+        }),
+        HighlightPreset::K8s => (
+            &[
+                r"\b(?:pods?|deployments?|replicasets?|statefulsets?|daemonsets?|jobs?|cronjobs?|services?|svc|configmaps?|secrets?|ingress(?:es)?|nodes?|namespaces?|ns|persistentvolumeclaims?|pvc|serviceaccounts?|pipelineruns?|taskruns?)(?:\.[a-z0-9.-]+)?/[a-z0-9](?:[-a-z0-9.]*[a-z0-9])?\b",
+            ],
+            |_| true,
+        ),
+        HighlightPreset::Path => (
+            &[r"(?:\.{0,2}/)?(?:[\w.-]+/)*[\w-]+\.[A-Za-z]\w{0,5}:\d+(?::\d+)?\b"],
+            |_| true,
+        ),
+        HighlightPreset::Http => (
+            &[
+                r"\b(?:GET|HEAD|POST|PUT|PATCH|DELETE|OPTIONS|CONNECT|TRACE)\b",
+                r#"(?:HTTP/\d(?:\.\d)?"?\s+|\b(?:status|code)(?:[_ -]?code)?"?[=:]\s*"?)(?P<hl>[1-5]\d\d)\b"#,
+            ],
+            |_| true,
+        ),
+        HighlightPreset::Duration => (
+            &[r"\b\d+(?:\.\d+)?(?:ns|us|µs|ms|s|m|h)(?:\d+(?:\.\d+)?(?:ns|us|µs|ms|s|m|h))*\b"],
+            |_| true,
+        ),
+        HighlightPreset::Quoted => (
+            &[
+                r#""(?:[^"\\]|\\.)*""#,
+                r"(?:^|\W)(?P<hl>'(?:[^'\\\n]|\\.)*')",
+            ],
+            |_| true,
+        ),
+        HighlightPreset::All => (&[], |_| true),
+    }
+}
+
+/// The `-r` regexps, compiled once at startup. The matches are computed on
+/// the text without its colors and when two of them overlap the one of the
+/// pattern given first wins.
+#[derive(Debug, Clone, Default)]
+pub struct Highlighter {
+    rules: Vec<Highlight>,
+}
+
+impl Highlighter {
+    /// Compile the patterns in order, see `pattern::compile` for their syntax.
+    pub fn compile(patterns: &[(String, Style)]) -> Result<Self, String> {
+        Ok(Self {
+            rules: patterns
+                .iter()
+                .map(|(pattern, style)| {
+                    crate::pattern::compile(pattern).map(|regex| Highlight::new(regex, *style))
+                })
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Add the `--highlight-preset` highlighters after the `-r` ones, in the
+    /// priority order of the presets whatever the order they were given in.
+    pub fn add_presets(&mut self, presets: &[HighlightPreset], theme: &crate::theme::Theme) {
+        let all = presets.contains(&HighlightPreset::All);
+        for preset in <HighlightPreset as clap::ValueEnum>::value_variants() {
+            if *preset == HighlightPreset::All || !(all || presets.contains(preset)) {
+                continue;
+            }
+            let name = clap::ValueEnum::to_possible_value(preset)
+                .map(|value| value.get_name().to_string())
+                .unwrap_or_default();
+            let (patterns, accept) = preset_patterns(*preset);
+            for pattern in patterns {
+                let regex = Regex::new(pattern).expect("valid highlight preset regexp");
+                self.rules.push(Highlight {
+                    accept,
+                    ..Highlight::new(regex, theme.preset(&name))
+                });
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The non overlapping matches of the rules sorted by position.
+    fn spans(&self, plain: &str) -> Vec<(usize, usize, Style)> {
+        let mut spans: Vec<(usize, usize, Style)> = Vec::new();
+        for rule in &self.rules {
+            for found in rule.matches(plain) {
+                let overlaps = spans
+                    .iter()
+                    .any(|(start, end, _)| found.start() < *end && *start < found.end());
+                if !found.is_empty() && !overlaps {
+                    spans.push((found.start(), found.end(), rule.style));
+                }
+            }
+        }
+        spans.sort_by_key(|(start, _, _)| *start);
+        spans
+    }
+
+    /// Paint the matches of a line which may already be colored, the colors
+    /// inside a match are replaced by its style and restored after it.
+    pub fn apply(&self, text: &str) -> String {
+        if self.rules.is_empty() {
+            return text.to_string();
+        }
+
+        let mut plain = String::with_capacity(text.len());
+        let mut escapes: Vec<(usize, &str)> = Vec::new();
+        let mut last = 0;
+        for escape in ANSI_RE.find_iter(text) {
+            plain.push_str(&text[last..escape.start()]);
+            escapes.push((plain.len(), escape.as_str()));
+            last = escape.end();
+        }
+        plain.push_str(&text[last..]);
+
+        let spans = self.spans(&plain);
+        if spans.is_empty() {
+            return text.to_string();
+        }
+
+        let mut rendered = String::with_capacity(text.len() * 2);
+        // The escapes in effect since the last reset, to restore them after
+        // a match.
+        let mut active = String::new();
+        let mut escapes = escapes.into_iter().peekable();
+        let mut position = 0;
+        for (start, end, style) in spans {
+            while let Some((at, escape)) = escapes.next_if(|(at, _)| *at <= start) {
+                rendered.push_str(&plain[position..at]);
+                rendered.push_str(escape);
+                record_escape(&mut active, escape);
+                position = at;
+            }
+            rendered.push_str(&plain[position..start]);
+            while let Some((_, escape)) = escapes.next_if(|(at, _)| *at < end) {
+                record_escape(&mut active, escape);
+            }
+            rendered.push_str(&plain[start..end].paint(style).to_string());
+            rendered.push_str(&active);
+            position = end;
+        }
+        for (at, escape) in escapes {
+            rendered.push_str(&plain[position..at]);
+            rendered.push_str(escape);
+            position = at;
+        }
+        rendered.push_str(&plain[position..]);
+        rendered
+    }
+}
+
+/// How a `--highlight-field`/`--highlight-line` rule compares the field value,
+/// the ordering ones only match numbers.
+#[derive(Debug, Clone)]
+enum Comparison {
+    Equal(String),
+    NotEqual(String),
+    Greater(f64),
+    GreaterOrEqual(f64),
+    Less(f64),
+    LessOrEqual(f64),
+    Matches(Regex),
+}
+
+/// A rule like `red:status>=500` styling a record by the value of a field,
+/// nested fields use dots.
+#[derive(Debug, Clone)]
+pub struct FieldHighlight {
+    pub field: String,
+    pub style: Style,
+    comparison: Comparison,
+}
+
+impl FieldHighlight {
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let (style, condition) = rule
+            .split_once(':')
+            .ok_or_else(|| format!("'{rule}' is not a style:condition rule"))?;
+        let style = crate::template::parse_style(style)?;
+        let start = condition
+            .find(['=', '!', '<', '>', '~'])
+            .filter(|start| *start > 0)
+            .ok_or_else(|| format!("'{condition}' has no field=value comparison"))?;
+        let (field, operator) = condition.split_at(start);
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("'{value}' is not a number in '{condition}'"))
+        };
+        let comparison = if let Some(value) = operator.strip_prefix(">=") {
+            Comparison::GreaterOrEqual(number(value)?)
+        } else if let Some(value) = operator.strip_prefix("<=") {
+            Comparison::LessOrEqual(number(value)?)
+        } else if let Some(value) = operator.strip_prefix("!=") {
+            Comparison::NotEqual(value.to_string())
+        } else if let Some(value) = operator.strip_prefix("==") {
+            Comparison::Equal(value.to_string())
+        } else if let Some(value) = operator.strip_prefix('=') {
+            Comparison::Equal(value.to_string())
+        } else if let Some(value) = operator.strip_prefix('>') {
+            Comparison::Greater(number(value)?)
+        } else if let Some(value) = operator.strip_prefix('<') {
+            Comparison::Less(number(value)?)
+        } else if let Some(pattern) = operator.strip_prefix('~') {
+            Comparison::Matches(crate::pattern::compile(pattern)?)
+        } else {
+            return Err(format!("'{condition}' has no field=value comparison"));
+        };
+        Ok(Self {
+            field: field.trim().to_string(),
+            style,
+            comparison,
+        })
+    }
+
+    pub fn matches(&self, value: &str) -> bool {
+        let number = || value.trim().parse::<f64>().ok();
+        match &self.comparison {
+            Comparison::Equal(expected) => value == expected,
+            Comparison::NotEqual(expected) => value != expected,
+            Comparison::Greater(limit) => number().is_some_and(|number| number > *limit),
+            Comparison::GreaterOrEqual(limit) => number().is_some_and(|number| number >= *limit),
+            Comparison::Less(limit) => number().is_some_and(|number| number < *limit),
+            Comparison::LessOrEqual(limit) => number().is_some_and(|number| number <= *limit),
+            Comparison::Matches(regex) => regex.is_match(value),
+        }
+    }
+}
+
+fn record_escape(active: &mut String, escape: &str) {
+    if matches!(escape, "\x1b[0m" | "\x1b[m") {
+        active.clear();
+    } else {
+        active.push_str(escape);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yansi::{Paint, Style};
+
+    use super::{FieldHighlight, Highlighter};
+    use crate::config::HighlightPreset;
+
+    fn preset_matches(preset: HighlightPreset, line: &str) -> Vec<String> {
+        let mut highlighter = Highlighter::default();
+        highlighter.add_presets(&[preset], &crate::theme::Theme::default());
+        highlighter
+            .spans(line)
+            .into_iter()
+            .map(|(start, end, _)| line[start..end].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn highlights_all_matches() {
+        let highlighter = Highlighter::compile(&[
+            (String::from("red"), Style::new().red()),
+            (String::from(r"\b(b.ue)\b"), Style::new().blue()),
+        ])
+        .unwrap();
+        assert_eq!(
+            highlighter.apply("red blue normal red"),
+            format!("{} {} normal {}", "red".red(), "blue".blue(), "red".red())
+        );
+    }
+
+    #[test]
+    fn invalid_regexps_are_errors() {
+        assert!(Highlighter::compile(&[(String::from("[invalid"), Style::new().red())]).is_err());
+    }
+
+    #[test]
+    fn first_pattern_wins_overlaps() {
+        let highlighter = Highlighter::compile(&[
+            (String::from("error: timeout"), Style::new().red()),
+            (String::from("timeout after"), Style::new().yellow()),
+            (String::from("m"), Style::new().blue()),
+        ])
+        .unwrap();
+        assert_eq!(
+            highlighter.apply("error: timeout after 5m"),
+            format!("{} after 5{}", "error: timeout".red(), "m".blue())
+        );
+    }
+
+    #[test]
+    fn matches_ignore_and_restore_existing_colors() {
+        let highlighter = Highlighter::compile(&[
+            (String::from("31"), Style::new().yellow()),
+            (String::from("ab"), Style::new().red()),
+        ])
+        .unwrap();
+        let line = format!("x{}y", "a31b".cyan());
+        assert_eq!(
+            highlighter.apply(&line),
+            format!("x\x1b[36ma{}\x1b[36mb\x1b[0my", "31".yellow())
+        );
+        let line = format!("{}{}", "a".cyan(), "b".green());
+        assert_eq!(
+            highlighter.apply(&line),
+            format!("\x1b[36m{}\x1b[32m\x1b[0m", "ab".red())
+        );
+    }
+
+    #[test]
+    fn presets_match_common_entities() {
+        for (preset, line, expected) in [
+            (
+                HighlightPreset::Url,
+                "fetching https://example.com/a?b=1 and ftp://x.org",
+                vec!["https://example.com/a?b=1", "ftp://x.org"],
+            ),
+            (
+                HighlightPreset::Uuid,
+                "run 123e4567-e89b-12d3-a456-426614174000 done",
+                vec!["123e4567-e89b-12d3-a456-426614174000"],
+            ),
+            (
+                HighlightPreset::Ip,
+                "from 10.0.0.1:8080 to fe80::1 and 2001:db8:0:0:0:0:2:1 not 999.1.1.1 or 12:30:45",
+                vec!["10.0.0.1:8080", "fe80::1", "2001:db8:0:0:0:0:2:1"],
+            ),
+            (
+                HighlightPreset::Sha,
+                "commit 3f2a9c1 and 1234567 deadbeef",
+                vec!["3f2a9c1"],
+            ),
+            (
+                HighlightPreset::K8s,
+                "restarting pod/my-app-5d8f and deployment.apps/web",
+                vec!["pod/my-app-5d8f", "deployment.apps/web"],
+            ),
+            (
+                HighlightPreset::Path,
+                "panic at src/main.rs:42:7 and ./lib/a.go:3",
+                vec!["src/main.rs:42:7", "./lib/a.go:3"],
+            ),
+            (
+                HighlightPreset::Http,
+                "GET /index HTTP/1.1 404 status=500 GETTER",
+                vec!["GET", "404", "500"],
+            ),
+            (
+                HighlightPreset::Duration,
+                "took 45ms then 1.2s and 2h30m not 5min",
+                vec!["45ms", "1.2s", "2h30m"],
+            ),
+            (
+                HighlightPreset::Quoted,
+                r#"said "hi \"you\"" and 'there' don't"#,
+                vec![r#""hi \"you\"""#, "'there'"],
+            ),
+        ] {
+            assert_eq!(preset_matches(preset, line), expected, "{preset:?}");
+        }
+    }
+
+    #[test]
+    fn presets_come_after_the_regexps() {
+        let mut highlighter =
+            Highlighter::compile(&[(String::from("example"), Style::new().red())]).unwrap();
+        highlighter.add_presets(&[HighlightPreset::All], &crate::theme::Theme::default());
+        let spans = highlighter.spans("see https://example.com in 5s");
+        assert_eq!(spans.len(), 2);
+        assert_eq!(&spans[0].0, &12);
+        assert_eq!(spans[0].2, Style::new().red());
+        assert_eq!(
+            spans[1].2,
+            crate::theme::Theme::default().preset("duration")
+        );
+    }
+
+    #[test]
+    fn field_highlights_compare_values() {
+        let server_error = FieldHighlight::parse("red,bold:status>=500").unwrap();
+        assert_eq!(server_error.field, "status");
+        assert_eq!(server_error.style, Style::new().red().bold());
+        assert!(server_error.matches("503"));
+        assert!(!server_error.matches("404"));
+        assert!(!server_error.matches("unknown"));
+
+        let prod = FieldHighlight::parse("magenta:kubernetes.namespace=prod").unwrap();
+        assert_eq!(prod.field, "kubernetes.namespace");
+        assert!(prod.matches("prod"));
+        assert!(!prod.matches("production"));
+        assert!(FieldHighlight::parse("red:user!=admin")
+            .unwrap()
+            .matches("bob"));
+        assert!(FieldHighlight::parse("red:path~^/api/")
+            .unwrap()
+            .matches("/api/v1"));
+
+        for invalid in [
+            "status>=500",
+            "red:status",
+            "red:=1",
+            "red:status>abc",
+            "nope:a=b",
+        ] {
+            assert!(FieldHighlight::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+}
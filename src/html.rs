@@ -0,0 +1,262 @@
+use std::fmt::Write as _;
+
+const PREAMBLE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>snazy</title>
+<style>
+body { background: #1e1e1e; color: #d4d4d4; margin: 1em; }
+.log { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 13px; }
+.line { white-space: pre-wrap; min-height: 1.2em; }
+details { margin: 0 0 0.3em 2em; }
+summary { cursor: pointer; color: #808080; font-style: italic; }
+</style>
+</head>
+<body>
+<div class="log">"#;
+
+const LINE_START: &str = "<div class=\"line\">";
+
+const EPILOGUE: &str = "</div>\n</body>\n</html>";
+
+/// The standard and bright colors of the xterm palette.
+const BASIC_COLORS: [&str; 16] = [
+    "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+    "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+];
+
+pub fn preamble() -> Vec<String> {
+    vec![PREAMBLE.to_string()]
+}
+
+pub fn epilogue() -> Vec<String> {
+    vec![EPILOGUE.to_string()]
+}
+
+/// A rendered line as an HTML block.
+pub fn line(line: &str) -> String {
+    format!("{LINE_START}{}</div>", ansi_to_html(line))
+}
+
+/// Insert a `--mark-field` prefix at the start of a line block, the
+/// collapsible block tags are left as they are.
+pub fn prefix_line(line: &str, prefix: &str) -> String {
+    match line.strip_prefix(LINE_START) {
+        Some(rest) => format!("{LINE_START}{}{rest}", ansi_to_html(prefix)),
+        None => line.to_string(),
+    }
+}
+
+/// A structured record, the expanded fields and the stacktrace are shown in
+/// collapsible blocks below the line.
+pub fn record(main: &str, fields: &[String], stacktrace: &[String]) -> Vec<String> {
+    let mut lines = vec![line(main)];
+    for (summary, block, open) in [("fields", fields, " open"), ("stacktrace", stacktrace, "")] {
+        if block.is_empty() {
+            continue;
+        }
+        lines.push(format!("<details{open}><summary>{summary}</summary>"));
+        lines.extend(block.iter().map(|block_line| line(block_line)));
+        lines.push("</details>".to_string());
+    }
+    lines
+}
+
+const BOLD: &str = "font-weight:bold;";
+const DIM: &str = "opacity:0.7;";
+const ITALIC: &str = "font-style:italic;";
+const UNDERLINE: &str = "text-decoration:underline;";
+
+#[derive(Default, Clone, PartialEq, Eq)]
+struct SgrState {
+    foreground: Option<String>,
+    background: Option<String>,
+    attributes: Vec<&'static str>,
+}
+
+impl SgrState {
+    fn css(&self) -> String {
+        let mut css = String::new();
+        if let Some(color) = self.foreground.as_ref() {
+            let _ = write!(css, "color:{color};");
+        }
+        if let Some(color) = self.background.as_ref() {
+            let _ = write!(css, "background-color:{color};");
+        }
+        for attribute in &self.attributes {
+            css.push_str(attribute);
+        }
+        css
+    }
+
+    fn set(&mut self, attribute: &'static str, enabled: bool) {
+        self.attributes.retain(|set| *set != attribute);
+        if enabled {
+            self.attributes.push(attribute);
+        }
+    }
+
+    fn apply(&mut self, parameters: &str) {
+        let codes: Vec<u16> = parameters
+            .split(';')
+            .map(|code| code.parse().unwrap_or(0))
+            .collect();
+        let mut index = 0;
+        while index < codes.len() {
+            match codes[index] {
+                0 => *self = Self::default(),
+                1 => self.set(BOLD, true),
+                2 => self.set(DIM, true),
+                3 => self.set(ITALIC, true),
+                4 => self.set(UNDERLINE, true),
+                22 => {
+                    self.set(BOLD, false);
+                    self.set(DIM, false);
+                }
+                23 => self.set(ITALIC, false),
+                24 => self.set(UNDERLINE, false),
+                code @ 30..=37 => {
+                    self.foreground = Some(BASIC_COLORS[usize::from(code - 30)].to_string());
+                }
+                code @ 90..=97 => {
+                    self.foreground = Some(BASIC_COLORS[usize::from(code - 82)].to_string());
+                }
+                code @ 40..=47 => {
+                    self.background = Some(BASIC_COLORS[usize::from(code - 40)].to_string());
+                }
+                code @ 100..=107 => {
+                    self.background = Some(BASIC_COLORS[usize::from(code - 92)].to_string());
+                }
+                39 => self.foreground = None,
+                49 => self.background = None,
+                code @ (38 | 48) => {
+                    let (color, used) = extended_color(&codes[index + 1..]);
+                    index += used;
+                    if code == 38 {
+                        self.foreground = color;
+                    } else {
+                        self.background = color;
+                    }
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+    }
+}
+
+/// Parse the `5;n` or `2;r;g;b` parameters following a 38 or 48 code,
+/// returns the color and the number of parameters used.
+fn extended_color(codes: &[u16]) -> (Option<String>, usize) {
+    match codes {
+        [5, index, ..] => (u8::try_from(*index).ok().map(palette_color), 2),
+        [2, red, green, blue, ..] => (Some(format!("#{red:02x}{green:02x}{blue:02x}")), 4),
+        _ => (None, codes.len()),
+    }
+}
+
+/// The color of an entry of the 256 colors xterm palette.
+fn palette_color(index: u8) -> String {
+    match index {
+        0..=15 => BASIC_COLORS[usize::from(index)].to_string(),
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(index / 36),
+                level((index / 6) % 6),
+                level(index % 6)
+            )
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            format!("#{gray:02x}{gray:02x}{gray:02x}")
+        }
+    }
+}
+
+/// Convert the ANSI SGR sequences of a line to styled spans and escape the
+/// HTML special characters, the other escape sequences are dropped.
+pub fn ansi_to_html(line: &str) -> String {
+    let mut html = String::new();
+    let mut state = SgrState::default();
+    let mut span_open = false;
+    let mut rest = line;
+
+    while let Some(start) = rest.find('\x1b') {
+        escape_into(&mut html, &rest[..start]);
+        let sequence = &rest[start + 1..];
+        let Some(parameters) = sequence.strip_prefix('[') else {
+            rest = sequence;
+            continue;
+        };
+        let Some(end) = parameters.find(|c: char| c.is_ascii_alphabetic()) else {
+            rest = "";
+            break;
+        };
+        if parameters[end..].starts_with('m') {
+            state.apply(&parameters[..end]);
+            if span_open {
+                html.push_str("</span>");
+                span_open = false;
+            }
+            let css = state.css();
+            if !css.is_empty() {
+                let _ = write!(html, "<span style=\"{css}\">");
+                span_open = true;
+            }
+        }
+        rest = &parameters[end + 1..];
+    }
+    escape_into(&mut html, rest);
+    if span_open {
+        html.push_str("</span>");
+    }
+    html
+}
+
+fn escape_into(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yansi::Paint;
+
+    #[test]
+    fn converts_ansi_colors_to_spans() {
+        let line = format!("{} <b> & {}", "ERROR".red().bold(), "x".fixed(208));
+        assert_eq!(
+            super::ansi_to_html(&line),
+            "<span style=\"color:#cd0000;font-weight:bold;\">ERROR</span> &lt;b&gt; &amp; <span style=\"color:#ff8700;\">x</span>"
+        );
+        assert_eq!(
+            super::ansi_to_html("\x1b[38;2;1;2;3mrgb\x1b[0m plain"),
+            "<span style=\"color:#010203;\">rgb</span> plain"
+        );
+    }
+
+    #[test]
+    fn records_have_collapsible_blocks() {
+        let lines = super::record("main", &[], &["at foo".to_string()]);
+        assert_eq!(
+            lines,
+            vec![
+                "<div class=\"line\">main</div>",
+                "<details><summary>stacktrace</summary>",
+                "<div class=\"line\">at foo</div>",
+                "</details>",
+            ]
+        );
+    }
+}
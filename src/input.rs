@@ -1,28 +1,50 @@
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Write};
-use std::sync::Arc;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::{mpsc, Arc};
 use std::time::{Duration, Instant};
 
 use crate::app::OutputEvent;
 use crate::config::Config;
+use crate::group::Grouper;
 use crate::parser::ParseState;
+use crate::split::SplitWriter;
 
 pub fn read_from_stdin(config: &Arc<Config>) {
     let stdin = io::stdin();
     let stdout = io::stdout();
     let mut stdout = io::BufWriter::new(stdout.lock());
-    process_reader(config, stdin.lock(), &mut stdout);
+    let mut split = SplitWriter::from_config(config);
+    write_lines(&mut stdout, crate::output::preamble(config));
+    process_reader(config, BufReader::new(stdin), &mut stdout, split.as_mut());
+    write_lines(&mut stdout, crate::output::epilogue(config));
 }
 
 pub fn read_from_files(config: &Arc<Config>) {
+    let mut split = SplitWriter::from_config(config);
+    write_lines(&mut io::stdout(), crate::output::preamble(config));
     for filename in config.files.as_ref().unwrap() {
         let stdout = io::stdout();
         let mut stdout = io::BufWriter::new(stdout.lock());
-        read_a_file(config, filename, &mut stdout);
+        if let Some(split) = split.as_mut() {
+            split.set_input(filename);
+        }
+        read_a_file(config, filename, &mut stdout, split.as_mut());
+    }
+    write_lines(&mut io::stdout(), crate::output::epilogue(config));
+}
+
+fn write_lines(writeto: &mut dyn Write, lines: Vec<String>) {
+    for line in lines {
+        writeln!(writeto, "{line}").unwrap();
     }
 }
 
-pub fn read_a_file(config: &Config, filename: &str, writeto: &mut dyn Write) {
+pub fn read_a_file(
+    config: &Config,
+    filename: &str,
+    writeto: &mut dyn Write,
+    split: Option<&mut SplitWriter>,
+) {
     let file = match File::open(filename) {
         Ok(file) => file,
         Err(error) => {
@@ -31,35 +53,182 @@ pub fn read_a_file(config: &Config, filename: &str, writeto: &mut dyn Write) {
         }
     };
 
-    process_reader(config, BufReader::new(file), writeto);
+    process_reader(config, BufReader::new(file), writeto, split);
 }
 
-pub fn process_reader(config: &Config, reader: impl BufRead, writeto: &mut dyn Write) {
+pub fn process_reader(
+    config: &Config,
+    reader: impl BufRead + Send + 'static,
+    writeto: &mut dyn Write,
+    split: Option<&mut SplitWriter>,
+) {
     let mut state = ParseState::default();
-    let flush_live_output = config.files.is_none();
-    let mut collapser = DuplicateCollapser::new(config);
+    let mut printer = Printer {
+        config,
+        writeto,
+        split,
+        collapser: DuplicateCollapser::new(config),
+        grouper: Grouper::new(config),
+        flush_live_output: config.files.is_none(),
+    };
 
-    for line in reader.lines() {
-        let Ok(line) = line else {
-            continue;
-        };
+    if config.group_idle.is_none() {
+        for line in InputLines::new(reader) {
+            printer.line(&line, &mut state);
+        }
+    } else {
+        // The lines are read from another thread so the idle groups are
+        // flushed on time while a quiet stream blocks the reader.
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in InputLines::new(reader) {
+                if sender.send(line).is_err() {
+                    return;
+                }
+            }
+        });
+        loop {
+            let received = match printer.grouper.next_idle_deadline() {
+                Some(deadline) => {
+                    lines.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => lines
+                    .recv()
+                    .map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(line) => printer.line(&line, &mut state),
+                Err(mpsc::RecvTimeoutError::Timeout) => printer.flush_idle(Instant::now()),
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
 
-        let Some(event) = crate::app::process_raw_line(config, &line, &mut state) else {
-            continue;
-        };
+    printer.finish(&mut state);
+}
+
+/// The lines of the input, the lines which are not valid UTF-8 are skipped.
+///
+/// The binary fields of a `journalctl -o export` stream, a bare key followed
+/// by the little endian length of the data and the data, are read as a
+/// `KEY=data` line whatever the data holds.
+struct InputLines<R> {
+    reader: R,
+    in_export: bool,
+}
+
+impl<R: BufRead> InputLines<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            in_export: false,
+        }
+    }
+
+    fn read_binary_field(&mut self, key: &str) -> Option<String> {
+        let mut length = [0; 8];
+        self.reader.read_exact(&mut length).ok()?;
+        let mut data = Vec::new();
+        (&mut self.reader)
+            .take(u64::from_le_bytes(length))
+            .read_to_end(&mut data)
+            .ok()?;
+        let mut newline = [0; 1];
+        let _ = self.reader.read_exact(&mut newline);
+        Some(format!("{key}={}", String::from_utf8_lossy(&data)))
+    }
+}
+
+impl<R: BufRead> Iterator for InputLines<R> {
+    type Item = String;
 
-        for rendered in collapser.push(event, Instant::now()) {
-            writeln!(writeto, "{rendered}").unwrap();
-            if flush_live_output {
-                writeto.flush().unwrap();
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let mut line = Vec::new();
+            if self.reader.read_until(b'\n', &mut line).ok()? == 0 {
+                return None;
+            }
+            if line.last() == Some(&b'\n') {
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
             }
+            let Ok(line) = String::from_utf8(line) else {
+                continue;
+            };
+
+            if self.in_export && crate::parser::is_journal_field_name(&line) {
+                return self.read_binary_field(&line);
+            }
+            self.in_export = if self.in_export {
+                !line.is_empty()
+            } else {
+                line.starts_with("__CURSOR=")
+            };
+            return Some(line);
         }
     }
+}
+
+/// Send the events of the input lines through the grouping and the
+/// duplicate collapsing to the output.
+struct Printer<'a> {
+    config: &'a Config,
+    writeto: &'a mut dyn Write,
+    split: Option<&'a mut SplitWriter>,
+    collapser: DuplicateCollapser<'a>,
+    grouper: Grouper<'a>,
+    flush_live_output: bool,
+}
+
+impl Printer<'_> {
+    fn line(&mut self, line: &str, state: &mut ParseState) {
+        let events = crate::app::process_raw_line(self.config, line, state);
+        self.events(events);
+    }
+
+    fn events(&mut self, events: Vec<OutputEvent>) {
+        for event in events {
+            if let Some(split) = self.split.as_mut() {
+                split.write(&event);
+            }
 
-    for rendered in collapser.finish() {
-        writeln!(writeto, "{rendered}").unwrap();
-        if flush_live_output {
-            writeto.flush().unwrap();
+            let now = Instant::now();
+            for event in self.grouper.push(event, now) {
+                let rendered = self.collapser.push(event, now);
+                self.write(rendered);
+            }
+        }
+    }
+
+    fn flush_idle(&mut self, now: Instant) {
+        for event in self.grouper.flush_idle(now) {
+            let rendered = self.collapser.push(event, now);
+            self.write(rendered);
+        }
+    }
+
+    fn finish(&mut self, state: &mut ParseState) {
+        let events = crate::app::finish_input(self.config, state);
+        self.events(events);
+        let now = Instant::now();
+        let mut remaining: Vec<String> = self
+            .grouper
+            .finish()
+            .into_iter()
+            .flat_map(|event| self.collapser.push(event, now))
+            .collect();
+        remaining.extend(self.collapser.finish());
+        self.write(remaining);
+    }
+
+    fn write(&mut self, rendered: Vec<String>) {
+        for rendered in rendered {
+            writeln!(self.writeto, "{rendered}").unwrap();
+            if self.flush_live_output {
+                self.writeto.flush().unwrap();
+            }
         }
     }
 }
@@ -139,7 +308,7 @@ impl<'a> DuplicateCollapser<'a> {
 
         let mut rendered = pending.rendered_lines;
         if pending.count > 1 {
-            rendered.push(crate::render::render_duplicate_summary(
+            rendered.extend(crate::render::render_duplicate_summary(
                 self.config,
                 pending.count,
                 self.window(),
@@ -175,7 +344,7 @@ mod tests {
             ..Config::default()
         };
         let mut output = Vec::new();
-        super::read_a_file(&config, file_path.to_str().unwrap(), &mut output);
+        super::read_a_file(&config, file_path.to_str().unwrap(), &mut output, None);
         file.close().unwrap();
 
         let output = std::str::from_utf8(&output).expect("Failed to convert output to utf8");
@@ -203,7 +372,12 @@ github.com/example/app.Function1(0x123456)
             ..Config::default()
         };
         let mut visible_output = Vec::new();
-        super::read_a_file(&visible, file_path.to_str().unwrap(), &mut visible_output);
+        super::read_a_file(
+            &visible,
+            file_path.to_str().unwrap(),
+            &mut visible_output,
+            None,
+        );
         let visible_output = std::str::from_utf8(&visible_output).unwrap();
         assert!(visible_output.contains("Stacktrace"));
         assert!(visible_output.contains("app.go"));
@@ -214,7 +388,12 @@ github.com/example/app.Function1(0x123456)
             ..Config::default()
         };
         let mut hidden_output = Vec::new();
-        super::read_a_file(&hidden, file_path.to_str().unwrap(), &mut hidden_output);
+        super::read_a_file(
+            &hidden,
+            file_path.to_str().unwrap(),
+            &mut hidden_output,
+            None,
+        );
         file.close().unwrap();
 
         let hidden_output = std::str::from_utf8(&hidden_output).unwrap();
@@ -231,7 +410,7 @@ github.com/example/app.Function1(0x123456)
             ..Config::default()
         };
         let mut missing_output = Vec::new();
-        super::read_a_file(&config, missing_path, &mut missing_output);
+        super::read_a_file(&config, missing_path, &mut missing_output, None);
         assert!(missing_output.is_empty());
 
         let file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
@@ -241,7 +420,12 @@ github.com/example/app.Function1(0x123456)
             ..Config::default()
         };
         let mut empty_output = Vec::new();
-        super::read_a_file(&config, file_path.to_str().unwrap(), &mut empty_output);
+        super::read_a_file(
+            &config,
+            file_path.to_str().unwrap(),
+            &mut empty_output,
+            None,
+        );
         file.close().unwrap();
         assert!(empty_output.is_empty());
     }
@@ -277,13 +461,114 @@ github.com/example/app.Function1(0x123456)
             flush_count: 0,
         };
 
-        super::process_reader(&config, input, &mut output);
+        super::process_reader(&config, input, &mut output, None);
 
         let rendered = std::str::from_utf8(&output.output).unwrap();
         assert!(rendered.contains("hello world"));
         assert_eq!(output.flush_count, 1);
     }
 
+    #[test]
+    fn input_lines_read_journal_export_binary_fields() {
+        let mut input = b"plain\n\xff bad\n__CURSOR=s=1\nPRIORITY=3\nMESSAGE\n".to_vec();
+        input.extend_from_slice(&12u64.to_le_bytes());
+        input.extend_from_slice(b"first\nsecond\n__REALTIME_TIMESTAMP=1\n\nAFTER\n");
+
+        let lines: Vec<String> = super::InputLines::new(Cursor::new(input.clone())).collect();
+        assert_eq!(
+            lines,
+            [
+                "plain",
+                "__CURSOR=s=1",
+                "PRIORITY=3",
+                "MESSAGE=first\nsecond",
+                "__REALTIME_TIMESTAMP=1",
+                "",
+                "AFTER"
+            ]
+        );
+
+        let config = Config {
+            coloring: crate::config::Coloring::Never,
+            ..Config::default()
+        };
+        let mut output = Vec::new();
+        super::process_reader(&config, Cursor::new(input), &mut output, None);
+        let rendered = std::str::from_utf8(&output).unwrap();
+        assert!(rendered.contains("ERROR"), "{rendered}");
+        assert!(rendered.contains("first\nsecond"), "{rendered}");
+    }
+
+    /// One line then nothing until the idle group was written, the input only
+    /// ends after that (or a timeout) like a quiet live stream.
+    struct QuietReader {
+        line: Option<&'static [u8]>,
+        flushed: std::sync::mpsc::Receiver<()>,
+        flushed_before_eof: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl std::io::Read for QuietReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if let Some(line) = self.line.take() {
+                buf[..line.len()].copy_from_slice(line);
+                return Ok(line.len());
+            }
+            if self.flushed.recv_timeout(Duration::from_secs(5)).is_ok() {
+                self.flushed_before_eof
+                    .store(true, std::sync::atomic::Ordering::SeqCst);
+            }
+            Ok(0)
+        }
+    }
+
+    struct HeaderWriter {
+        output: Vec<u8>,
+        sender: std::sync::mpsc::Sender<()>,
+    }
+
+    impl std::io::Write for HeaderWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf.starts_with("──".as_bytes()) {
+                let _ = self.sender.send(());
+            }
+            self.output.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn idle_groups_are_flushed_while_the_input_is_quiet() {
+        let config = Config {
+            files: None,
+            group_by: Some("trace_id".to_string()),
+            group_idle: Some(Duration::from_millis(50)),
+            coloring: crate::config::Coloring::Never,
+            ..Config::default()
+        };
+        let (sender, flushed) = std::sync::mpsc::channel();
+        let flushed_before_eof = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let input = std::io::BufReader::new(QuietReader {
+            line: Some(b"{\"level\":\"info\",\"msg\":\"a1\",\"trace_id\":\"a\"}\n"),
+            flushed,
+            flushed_before_eof: flushed_before_eof.clone(),
+        });
+        let mut output = HeaderWriter {
+            output: Vec::new(),
+            sender,
+        };
+
+        super::process_reader(&config, input, &mut output, None);
+
+        assert!(flushed_before_eof.load(std::sync::atomic::Ordering::SeqCst));
+        let rendered = std::str::from_utf8(&output.output).unwrap();
+        assert!(rendered.starts_with("── trace_id=a ──"));
+        assert!(rendered.contains("a1"));
+    }
+
     #[test]
     fn duplicate_collapser_flushes_single_lines_unchanged() {
         let config = Config {
@@ -376,6 +661,7 @@ github.com/example/app.Function1(0x123456)
         OutputEvent {
             collapse_key: collapse_key.map(ToOwned::to_owned),
             rendered_lines: vec![line.to_string()],
+            parsed: None,
         }
     }
 }
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+pub const SYMBOL_SETS: [&str; 3] = ["emoji", "nerd", "ascii"];
+
+/// The `--level-symbols` of each level, `OTHER` is used for the levels
+/// which are not in the set.
+const EMOJI: &[(&str, &str)] = &[
+    ("TRACE", "🔍"),
+    ("DEBUG", "🐛"),
+    ("INFO", "💡"),
+    ("NOTICE", "📣"),
+    ("WARNING", "⚠️"),
+    ("ERROR", "🚨"),
+    ("CRITICAL", "🔥"),
+    ("FATAL", "💀"),
+    ("OTHER", "∙"),
+];
+
+const NERD: &[(&str, &str)] = &[
+    ("TRACE", "\u{f002}"),
+    ("DEBUG", "\u{f188}"),
+    ("INFO", "\u{f05a}"),
+    ("NOTICE", "\u{f0f3}"),
+    ("WARNING", "\u{f071}"),
+    ("ERROR", "\u{f057}"),
+    ("CRITICAL", "\u{f0e7}"),
+    ("FATAL", "\u{f068c}"),
+    ("OTHER", "\u{f10c}"),
+];
+
+const ASCII: &[(&str, &str)] = &[
+    ("TRACE", "[T]"),
+    ("DEBUG", "[D]"),
+    ("INFO", "[I]"),
+    ("NOTICE", "[N]"),
+    ("WARNING", "[W]"),
+    ("ERROR", "[E]"),
+    ("CRITICAL", "[C]"),
+    ("FATAL", "[F]"),
+    ("OTHER", "[?]"),
+];
+
+/// A symbol set of the config file, it starts from the `inherits` set (emoji
+/// by default) and overrides the symbols of some levels.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SymbolSetDefinition {
+    pub inherits: Option<String>,
+    #[serde(flatten)]
+    pub symbols: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolSet {
+    symbols: HashMap<String, String>,
+}
+
+impl Default for SymbolSet {
+    fn default() -> Self {
+        Self::builtin("emoji").unwrap()
+    }
+}
+
+impl SymbolSet {
+    pub fn builtin(name: &str) -> Option<Self> {
+        let symbols = match name {
+            "emoji" => EMOJI,
+            "nerd" => NERD,
+            "ascii" => ASCII,
+            _ => return None,
+        };
+        Some(Self {
+            symbols: symbols
+                .iter()
+                .map(|(level, symbol)| ((*level).to_string(), (*symbol).to_string()))
+                .collect(),
+        })
+    }
+
+    /// Look up a symbol set by name, the config file sets come first so they
+    /// can replace a builtin one.
+    pub fn load(
+        name: &str,
+        definitions: &HashMap<String, SymbolSetDefinition>,
+    ) -> Result<Self, String> {
+        Self::load_inherited(name, definitions, &mut Vec::new())
+    }
+
+    fn load_inherited(
+        name: &str,
+        definitions: &HashMap<String, SymbolSetDefinition>,
+        seen: &mut Vec<String>,
+    ) -> Result<Self, String> {
+        let Some(definition) = definitions.get(name) else {
+            return Self::builtin(name).ok_or_else(|| {
+                let mut names: Vec<&str> = SYMBOL_SETS.to_vec();
+                names.extend(definitions.keys().map(String::as_str));
+                format!(
+                    "unknown symbol set '{name}', available: {}",
+                    names.join(", ")
+                )
+            });
+        };
+        if seen.iter().any(|seen| seen == name) {
+            return Err(format!("symbol set '{name}' inherits from itself"));
+        }
+        seen.push(name.to_string());
+
+        let parent = definition.inherits.as_deref().unwrap_or("emoji");
+        let mut set = if definitions.contains_key(parent) {
+            Self::load_inherited(parent, definitions, seen)?
+        } else {
+            Self::builtin(parent).ok_or_else(|| {
+                format!("symbol set '{name}' inherits from unknown set '{parent}'")
+            })?
+        };
+        for (level, symbol) in &definition.symbols {
+            set.symbols.insert(level.to_uppercase(), symbol.clone());
+        }
+        Ok(set)
+    }
+
+    /// The symbol of a normalized level like `WARNING`.
+    pub fn symbol(&self, level: &str) -> &str {
+        self.symbols
+            .get(level)
+            .or_else(|| self.symbols.get("OTHER"))
+            .map_or("", String::as_str)
+    }
+}
+
+/// A level label of the config file, the text replaces the level name and the
+/// width pads it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LevelLabel {
+    pub text: Option<String>,
+    pub width: Option<usize>,
+}
+
+/// The text shown for a level, `WARNING` is shortened to `WARN` unless a
+/// label says otherwise.
+pub fn label_text(labels: &HashMap<String, LevelLabel>, level: &str) -> String {
+    labels
+        .get(level)
+        .and_then(|label| label.text.clone())
+        .unwrap_or_else(|| match level {
+            "WARNING" => "WARN".to_string(),
+            level => level.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{LevelLabel, SymbolSet, SymbolSetDefinition, SYMBOL_SETS};
+
+    #[test]
+    fn builtin_symbol_sets() {
+        let emoji = SymbolSet::default();
+        assert_eq!(emoji.symbol("DEBUG"), "🐛");
+        assert_eq!(emoji.symbol("WARNING"), "⚠️");
+        assert_eq!(emoji.symbol("ERROR"), "🚨");
+        assert_eq!(emoji.symbol("INFO"), "💡");
+        assert_eq!(emoji.symbol("FATAL"), "💀");
+        assert_eq!(emoji.symbol("UNKNOWN"), "∙");
+        for name in SYMBOL_SETS {
+            let set = SymbolSet::builtin(name).unwrap();
+            assert!(!set.symbol("CRITICAL").is_empty(), "{name}");
+        }
+        assert_eq!(SymbolSet::builtin("ascii").unwrap().symbol("TRACE"), "[T]");
+    }
+
+    #[test]
+    fn config_file_symbol_sets() {
+        let definitions: HashMap<String, SymbolSetDefinition> = serde_json::from_str(
+            r#"{"short": {"inherits": "ascii", "error": "E!", "other": "?"}, "loop": {"inherits": "loop"}}"#,
+        )
+        .unwrap();
+        let short = SymbolSet::load("short", &definitions).unwrap();
+        assert_eq!(short.symbol("ERROR"), "E!");
+        assert_eq!(short.symbol("INFO"), "[I]");
+        assert_eq!(short.symbol("VERBOSE"), "?");
+        assert!(SymbolSet::load("loop", &definitions).is_err());
+        assert!(SymbolSet::load("nope", &definitions).is_err());
+    }
+
+    #[test]
+    fn label_texts() {
+        let labels = HashMap::from([(
+            "NOTICE".to_string(),
+            LevelLabel {
+                text: Some("NOTE".to_string()),
+                width: None,
+            },
+        )]);
+        assert_eq!(super::label_text(&labels, "NOTICE"), "NOTE");
+        assert_eq!(super::label_text(&labels, "WARNING"), "WARN");
+        assert_eq!(super::label_text(&labels, "TRACE"), "TRACE");
+    }
+}
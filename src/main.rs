@@ -9,11 +9,21 @@ use std::sync::Arc;
 mod app;
 mod cli;
 mod config;
+mod config_file;
+mod group;
+mod highlight;
+mod html;
 mod input;
+mod levels;
 mod model;
+mod output;
 mod parser;
+mod pattern;
 mod pipeline;
 mod render;
+mod split;
+mod template;
+mod theme;
 mod utils;
 
 fn main() {
@@ -1,3 +1,6 @@
+use std::fmt;
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
 use serde_json::Value;
 
@@ -13,6 +16,67 @@ pub struct StructuredLog {
     pub stacktrace: Option<String>,
     pub raw_json: Option<Value>,
     pub kail_prefix: Option<String>,
+    pub source: Option<KailSource>,
+    pub access: Option<AccessInfo>,
+}
+
+/// Status and latency of an access log, used for highlighting and filtering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessInfo {
+    pub status: u16,
+    pub duration: Option<Duration>,
+    /// Where the status code was written when composing the record.
+    pub status_span: Option<TextSpan>,
+    /// Where the latency was written when composing the record.
+    pub latency_span: Option<TextSpan>,
+}
+
+/// A byte range in the message or in the `others` of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextSpan {
+    pub part: RecordPart,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl TextSpan {
+    /// The span of `text` about to be appended to `composed`.
+    pub fn appended(part: RecordPart, composed: &str, text: &str) -> Self {
+        Self {
+            part,
+            start: composed.len(),
+            end: composed.len() + text.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordPart {
+    Message,
+    Others,
+}
+
+/// The level of a non structured line and the position of the text it was
+/// inferred from, to color it. The span is empty when the level is inherited
+/// from the previous line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawLevel {
+    pub level: &'static str,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KailSource {
+    pub namespace: String,
+    pub pod: String,
+    pub container: String,
+}
+
+impl fmt::Display for KailSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}[{}]", self.namespace, self.pod, self.container)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -25,9 +89,11 @@ pub struct KubectlEvent {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+// structured logs are the common case, boxing them would only add an allocation per line
+#[allow(clippy::large_enum_variant)]
 pub enum ParsedLine {
     Structured(StructuredLog),
-    Raw(String),
+    Raw(String, Option<RawLevel>),
     KubectlHeader,
     KubectlEvent(KubectlEvent),
 }
@@ -39,5 +105,6 @@ pub struct RenderedLog {
     pub delta: String,
     pub others: String,
     pub message: String,
+    pub fields: Vec<String>,
     pub stacktrace: Option<String>,
 }
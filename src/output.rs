@@ -0,0 +1,428 @@
+use yansi::Paint;
+
+use crate::config::{Config, OutputFormat};
+use crate::model::StructuredLog;
+
+const DEFAULT_COLUMNS: [&str; 3] = ["ts", "level", "msg"];
+const DEFAULT_FIELD_WIDTH: usize = 12;
+const MIN_MESSAGE_WIDTH: usize = 20;
+
+/// A `--columns` entry, `name:width` sets the width of a field column.
+#[derive(Debug, Clone)]
+struct Column {
+    name: String,
+    width: Option<usize>,
+}
+
+fn columns(config: &Config) -> Vec<Column> {
+    let columns: Vec<&str> = if config.columns.is_empty() {
+        DEFAULT_COLUMNS.to_vec()
+    } else {
+        config.columns.iter().map(String::as_str).collect()
+    };
+    columns
+        .into_iter()
+        .map(|column| match column.rsplit_once(':') {
+            Some((name, width)) if width.parse::<usize>().is_ok() => Column {
+                name: name.to_string(),
+                width: width.parse().ok(),
+            },
+            _ => Column {
+                name: column.to_string(),
+                width: None,
+            },
+        })
+        .collect()
+}
+
+/// The columns of the table, csv and tsv outputs with their widths, computed
+/// once for all the records.
+#[derive(Debug, Clone)]
+pub struct TableLayout {
+    columns: Vec<Column>,
+    widths: Vec<usize>,
+}
+
+fn layout(config: &Config) -> &TableLayout {
+    config.table_layout.get_or_init(|| {
+        let columns = columns(config);
+        let widths = column_widths(config, &columns);
+        TableLayout { columns, widths }
+    })
+}
+
+fn is_message(column: &Column) -> bool {
+    matches!(column.name.as_str(), "msg" | "message")
+}
+
+/// The lines written once before the records.
+pub fn preamble(config: &Config) -> Vec<String> {
+    match config.output {
+        OutputFormat::Text => Vec::new(),
+        OutputFormat::Table => {
+            let TableLayout { columns, widths } = layout(config);
+            let header = columns
+                .iter()
+                .zip(widths)
+                .map(|(column, width)| fit(&column.name.to_uppercase(), *width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            let rule = widths
+                .iter()
+                .map(|width| "─".repeat(*width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            match config.coloring {
+                crate::config::Coloring::Never => vec![header.trim_end().to_string(), rule],
+                _ => vec![
+                    header.trim_end().paint(config.theme.header).to_string(),
+                    rule.paint(config.theme.rule).to_string(),
+                ],
+            }
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => vec![delimited_row(
+            config.output,
+            layout(config)
+                .columns
+                .iter()
+                .map(|column| column.name.clone()),
+        )],
+        OutputFormat::Html => crate::html::preamble(),
+    }
+}
+
+/// The lines written once after the records.
+pub fn epilogue(config: &Config) -> Vec<String> {
+    match config.output {
+        OutputFormat::Html => crate::html::epilogue(),
+        _ => Vec::new(),
+    }
+}
+
+/// Render a record as a CSV or TSV row, the timestamps are always written in
+/// RFC 3339 whatever the `--time-format` is, and left empty when they could
+/// not be parsed.
+pub fn render_delimited_row(config: &Config, log: &StructuredLog) -> String {
+    delimited_row(
+        config.output,
+        layout(config)
+            .columns
+            .iter()
+            .map(|column| match column.name.as_str() {
+                "ts" | "time" | "timestamp" => log
+                    .parsed_timestamp
+                    .map(|timestamp| timestamp.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true))
+                    .unwrap_or_default(),
+                name => column_value(log, name),
+            }),
+    )
+}
+
+/// CSV values are quoted as in RFC 4180, TSV ones have their tabs and line
+/// breaks escaped.
+fn delimited_row(format: OutputFormat, values: impl Iterator<Item = String>) -> String {
+    let values: Vec<String> = values
+        .map(|value| match format {
+            OutputFormat::Tsv => value
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r"),
+            _ if value.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            }
+            _ => value,
+        })
+        .collect();
+    values.join(if format == OutputFormat::Tsv {
+        "\t"
+    } else {
+        ","
+    })
+}
+
+/// The value of a column, the builtin names come from the structured log and
+/// anything else is looked up in the JSON like `--include-fields`.
+pub fn column_value(log: &StructuredLog, column: &str) -> String {
+    match column {
+        "ts" | "time" | "timestamp" => log.timestamp.clone().unwrap_or_default(),
+        "level" => match log.level.as_str() {
+            "WARNING" => "WARN".to_string(),
+            level => level.to_string(),
+        },
+        "msg" | "message" => log.message.clone(),
+        "stacktrace" => log.stacktrace.clone().unwrap_or_default(),
+        "others" => log.others.as_deref().unwrap_or_default().trim().to_string(),
+        "prefix" => log.kail_prefix.clone().unwrap_or_default(),
+        "source" => log
+            .source
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        path => log
+            .raw_json
+            .as_ref()
+            .and_then(|raw_json| crate::pipeline::get_nested_value(raw_json, path))
+            .map(|value| match value {
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            })
+            .unwrap_or_default(),
+    }
+}
+
+/// The message column takes the width left by the other columns, the width
+/// of the terminal or `--width`.
+fn column_widths(config: &Config, columns: &[Column]) -> Vec<usize> {
+    let mut widths: Vec<usize> = columns
+        .iter()
+        .map(|column| {
+            let width = column.width.unwrap_or(match column.name.as_str() {
+                "ts" | "time" | "timestamp" => crate::utils::format_timestamp(
+                    &chrono::Utc::now(),
+                    config.time_format.as_str(),
+                    config.timezone.as_deref(),
+                )
+                .chars()
+                .count(),
+                "level" => 5,
+                _ if is_message(column) => 0,
+                _ => DEFAULT_FIELD_WIDTH,
+            });
+            width.max(column.name.chars().count())
+        })
+        .collect();
+
+    if let Some(index) = columns.iter().position(is_message) {
+        if columns[index].width.is_none() {
+            let used: usize =
+                widths.iter().sum::<usize>() - widths[index] + 2 * (columns.len() - 1);
+            widths[index] = config.width.saturating_sub(used).max(MIN_MESSAGE_WIDTH);
+        }
+    }
+    widths
+}
+
+/// Render a record as a table row, the message column is wrapped on several
+/// lines and the other columns are truncated.
+pub fn render_table_row(config: &Config, log: &StructuredLog) -> Vec<String> {
+    let TableLayout { columns, widths } = layout(config);
+    let cells: Vec<Vec<String>> = columns
+        .iter()
+        .zip(widths)
+        .map(|(column, width)| {
+            let value = column_value(log, &column.name).replace('\n', " ");
+            if is_message(column) {
+                wrap(&value, *width)
+            } else {
+                vec![fit(&value, *width)]
+            }
+        })
+        .collect();
+
+    let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+    let highlight = crate::render::line_highlight(config, log);
+    (0..height)
+        .map(|row| {
+            let line = columns
+                .iter()
+                .zip(&cells)
+                .zip(widths)
+                .map(|((column, cell), width)| {
+                    let text = cell.get(row).cloned().unwrap_or_else(|| " ".repeat(*width));
+                    match highlight {
+                        Some(_) => text,
+                        None => style_cell(config, log, column, text),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ");
+            let line = line.trim_end();
+            highlight.map_or_else(|| line.to_string(), |style| line.paint(style).to_string())
+        })
+        .collect()
+}
+
+fn style_cell(config: &Config, log: &StructuredLog, column: &Column, text: String) -> String {
+    if config.coloring == crate::config::Coloring::Never {
+        return text;
+    }
+    match column.name.as_str() {
+        "level" => text.paint(config.theme.level(&log.level)).to_string(),
+        "ts" | "time" | "timestamp" => text.paint(config.theme.timestamp).to_string(),
+        "others" => text.paint(config.theme.others).to_string(),
+        "prefix" | "source" => text.paint(config.theme.prefix).to_string(),
+        _ if is_message(column) && !config.regexp_colours.is_empty() => {
+            config.regexp_colours.apply(&text)
+        }
+        _ => text,
+    }
+}
+
+/// Pad or truncate a value to exactly `width` characters.
+fn fit(value: &str, width: usize) -> String {
+    let count = value.chars().count();
+    if count > width {
+        let mut truncated: String = value.chars().take(width.saturating_sub(1)).collect();
+        truncated.push('…');
+        truncated
+    } else {
+        format!("{value}{}", " ".repeat(width - count))
+    }
+}
+
+/// Wrap a value on words into lines of `width` characters, words longer
+/// than the width are cut.
+fn wrap(value: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in value.split_whitespace() {
+        let mut word = word.to_string();
+        loop {
+            let current_count = current.chars().count();
+            let separator = usize::from(current_count > 0);
+            if current_count + separator + word.chars().count() <= width {
+                if separator == 1 {
+                    current.push(' ');
+                }
+                current.push_str(&word);
+                break;
+            }
+            if current_count > 0 {
+                lines.push(fit(&std::mem::take(&mut current), width));
+                continue;
+            }
+            let head: String = word.chars().take(width).collect();
+            word = word.chars().skip(width).collect();
+            lines.push(head);
+            if word.is_empty() {
+                break;
+            }
+        }
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(fit(&current, width));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{Coloring, Config, OutputFormat};
+    use crate::model::StructuredLog;
+
+    fn log(message: &str) -> StructuredLog {
+        StructuredLog {
+            level: "WARNING".to_string(),
+            message: message.to_string(),
+            timestamp: Some("10:00:00".to_string()),
+            parsed_timestamp: None,
+            others: None,
+            consumed_fields: Vec::new(),
+            extra_fields: Vec::new(),
+            stacktrace: None,
+            raw_json: Some(serde_json::json!({"component": "controller-manager", "status": 503})),
+            kail_prefix: None,
+            source: None,
+            access: None,
+        }
+    }
+
+    #[test]
+    fn table_rows_are_aligned_and_wrapped() {
+        let config = Config {
+            output: OutputFormat::Table,
+            columns: vec![
+                "ts".to_string(),
+                "level".to_string(),
+                "component:10".to_string(),
+                "msg".to_string(),
+                "status".to_string(),
+            ],
+            width: 60,
+            coloring: Coloring::Never,
+            time_format: "%H:%M:%S".to_string(),
+            ..Config::default()
+        };
+
+        assert_eq!(
+            super::preamble(&config),
+            vec![
+                "TS        LEVEL  COMPONENT   MSG                   STATUS",
+                "────────  ─────  ──────────  ────────────────────  ────────────",
+            ]
+        );
+        assert_eq!(
+            super::render_table_row(&config, &log("the upstream did not answer in time")),
+            vec![
+                "10:00:00  WARN   controlle…  the upstream did not  503",
+                "                             answer in time",
+            ]
+        );
+    }
+
+    #[test]
+    fn table_rows_take_the_line_highlight() {
+        use yansi::Paint;
+
+        let config = Config {
+            output: OutputFormat::Table,
+            columns: vec!["level".to_string(), "msg".to_string()],
+            width: 40,
+            line_highlights: vec![
+                crate::highlight::FieldHighlight::parse("red:status>=500").unwrap()
+            ],
+            ..Config::default()
+        };
+
+        assert_eq!(
+            super::render_table_row(&config, &log("down")),
+            vec!["WARN   down".red().to_string()]
+        );
+    }
+
+    #[test]
+    fn delimited_rows_are_escaped() {
+        let config = Config {
+            output: OutputFormat::Csv,
+            columns: vec!["ts".to_string(), "msg".to_string(), "status".to_string()],
+            ..Config::default()
+        };
+        let mut record = log("said \"hi\", then\nleft");
+        record.parsed_timestamp = chrono::DateTime::parse_from_rfc3339("2022-04-25T14:20:32.5Z")
+            .ok()
+            .map(|timestamp| timestamp.with_timezone(&chrono::Utc));
+
+        assert_eq!(super::preamble(&config), vec!["ts,msg,status".to_string()]);
+        assert_eq!(
+            super::render_delimited_row(&config, &record),
+            "2022-04-25T14:20:32.500Z,\"said \"\"hi\"\", then\nleft\",503"
+        );
+
+        let config = Config {
+            output: OutputFormat::Tsv,
+            ..config
+        };
+        assert_eq!(
+            super::render_delimited_row(&config, &record),
+            "2022-04-25T14:20:32.500Z\tsaid \"hi\", then\\nleft\t503"
+        );
+
+        // Without a parsed timestamp the display one is not written.
+        record.parsed_timestamp = None;
+        assert!(record.timestamp.is_some());
+        assert_eq!(
+            super::render_delimited_row(&config, &record),
+            "\tsaid \"hi\", then\\nleft\t503"
+        );
+    }
+
+    #[test]
+    fn wrap_cuts_long_words() {
+        assert_eq!(
+            super::wrap("abcdefgh ij", 4),
+            vec!["abcd".to_string(), "efgh".to_string(), "ij  ".to_string()]
+        );
+        assert_eq!(super::wrap("", 3), vec!["   ".to_string()]);
+    }
+}
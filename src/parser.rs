@@ -1,4 +1,6 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::LazyLock;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
 use regex::Regex;
@@ -6,10 +8,25 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::config::Config;
-use crate::model::{KubectlEvent, ParsedLine, StructuredLog};
-
-pub const KAIL_RE: &str =
-    r"^(?P<namespace>[^/]*)/(?P<pod>[^\[]*)\[(?P<container>[^]]*)]: (?P<line>.*)";
+use crate::model::{
+    AccessInfo, KailSource, KubectlEvent, ParsedLine, RecordPart, StructuredLog, TextSpan,
+};
+
+mod access;
+mod container;
+mod frameworks;
+mod journald;
+mod klog;
+mod otlp;
+mod raw_level;
+mod syslog;
+
+pub(crate) use journald::is_field_name as is_journal_field_name;
+
+pub static KAIL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<namespace>[^/]*)/(?P<pod>[^\[]*)\[(?P<container>[^]]*)]: (?P<line>.*)")
+        .expect("valid kail regexp")
+});
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Pac {
@@ -57,6 +74,12 @@ pub struct ParseState {
     pub kubectl_events_mode: bool,
     pub kubectl_events_cols: Option<(usize, usize, usize, usize, usize)>,
     pub previous_structured_timestamp: Option<DateTime<Utc>>,
+    pub(crate) cri_partials: HashMap<(String, String), PreparedLine>,
+    pub journal_export: Option<serde_json::Map<String, Value>>,
+    pub followed_values: HashSet<String>,
+    pub(crate) klog_values: HashMap<String, PreparedLine>,
+    pub raw_levels: HashMap<String, &'static str>,
+    pub completed: Vec<ParsedLine>,
 }
 
 struct StructuredFields<'a> {
@@ -69,13 +92,53 @@ struct StructuredFields<'a> {
     consumed_fields: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct PreparedLine {
     line: String,
     kail_prefix: Option<String>,
+    source: Option<KailSource>,
+    wrapper: Option<container::ContainerWrapper>,
+}
+
+/// Parse a raw line which can hold several records, like OTLP JSON batches.
+pub fn parse_lines(config: &Config, rawline: &str, state: &mut ParseState) -> Vec<ParsedLine> {
+    if state.journal_export.is_none() && rawline.contains("\"resourceLogs\"") {
+        if let Some(logs) = otlp::parse_otlp_batch(config, &prepare_line(config, rawline)) {
+            return logs.into_iter().map(ParsedLine::Structured).collect();
+        }
+    }
+
+    let parsed = parse_line(config, rawline, state);
+    let mut lines = std::mem::take(&mut state.completed);
+    lines.extend(parsed);
+    lines
+}
+
+/// The records still buffered at the end of the input, like a CRI partial
+/// line or a klog record whose multi-line value was never closed.
+pub fn finish(config: &Config, state: &mut ParseState) -> Vec<ParsedLine> {
+    for prepared in container::take_partials(state) {
+        let parsed = parse_prepared(config, &prepared, &prepared.line, state);
+        state.completed.push(parsed);
+    }
+    if let Some(record) = journald::take_export_record(state) {
+        let parsed = export_record(config, &record);
+        state.completed.push(parsed);
+    }
+    for prepared in klog::take_pending(state) {
+        let parsed = parse_prepared(config, &prepared, &prepared.line, state);
+        state.completed.push(parsed);
+    }
+    std::mem::take(&mut state.completed)
 }
 
 pub fn parse_line(config: &Config, rawline: &str, state: &mut ParseState) -> Option<ParsedLine> {
+    match journald::feed_export_line(rawline, state) {
+        journald::ExportFeed::NotExport => {}
+        journald::ExportFeed::Buffered => return None,
+        journald::ExportFeed::Record(record) => return Some(export_record(config, &record)),
+    }
+
     if rawline.trim().is_empty() {
         return None;
     }
@@ -91,26 +154,69 @@ pub fn parse_line(config: &Config, rawline: &str, state: &mut ParseState) -> Opt
         }
     }
 
-    let prepared = prepare_line(config, rawline);
+    let prepared = container::unwrap_container_line(prepare_line(config, rawline), state)?;
+    let mut ready = klog::join_multiline_values(prepared, state);
+    let prepared = ready.pop()?;
+    for interrupted in ready {
+        let parsed = parse_prepared(config, &interrupted, &interrupted.line, state);
+        state.completed.push(parsed);
+    }
+    Some(parse_prepared(config, &prepared, rawline, state))
+}
 
-    parse_structured_log(config, &prepared).map_or_else(
-        || Some(ParsedLine::Raw(rawline.to_string())),
-        |log| Some(ParsedLine::Structured(log)),
+/// A `journalctl -o export` record, shown as is when it has no message.
+fn export_record(config: &Config, record: &Value) -> ParsedLine {
+    journald::parse_export_record(config, record).map_or_else(
+        || ParsedLine::Raw(record.to_string(), None),
+        ParsedLine::Structured,
     )
 }
 
+fn parse_prepared(
+    config: &Config,
+    prepared: &PreparedLine,
+    rawline: &str,
+    state: &mut ParseState,
+) -> ParsedLine {
+    let log = parse_structured_log(config, prepared);
+    let source = prepared.source_key();
+
+    if let Some(log) = container::apply_wrapper_fallbacks(config, prepared, log) {
+        state.raw_levels.remove(&source);
+        return ParsedLine::Structured(log);
+    }
+    let level = raw_level::line_level(rawline, source, state);
+    ParsedLine::Raw(rawline.to_string(), level)
+}
+
+impl PreparedLine {
+    /// The kail source of the line, the lines of a record are buffered per
+    /// source as the logs of several containers are interleaved.
+    fn source_key(&self) -> String {
+        self.source
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    }
+}
+
 pub fn prepare_line(config: &Config, rawline: &str) -> PreparedLine {
-    let kail_prefix = parse_kail_lines(config, rawline);
+    let source = parse_kail_source(rawline);
+    let kail_prefix = source
+        .as_ref()
+        .map(|source| format_kail_prefix(config, source));
     let line = if kail_prefix.is_some() {
-        Regex::new(KAIL_RE).map_or_else(
-            |_| rawline.to_string(),
-            |re| re.replace_all(rawline, "$line").to_string(),
-        )
+        strip_kail_prefix(rawline)
     } else {
         rawline.to_string()
     };
 
-    PreparedLine { line, kail_prefix }
+    PreparedLine {
+        line,
+        kail_prefix,
+        source,
+        wrapper: None,
+    }
 }
 
 pub fn parse_structured_log(config: &Config, prepared: &PreparedLine) -> Option<StructuredLog> {
@@ -126,11 +232,25 @@ pub fn parse_structured_log(config: &Config, prepared: &PreparedLine) -> Option<
 
     parse_pac(prepared, raw_json.clone(), time_format, timezone)
         .or_else(|| parse_caddy(prepared, raw_json.as_ref(), time_format, timezone))
+        .or_else(|| access::parse_traefik(prepared, raw_json.as_ref(), time_format, timezone))
+        .or_else(|| access::parse_envoy_json(prepared, raw_json.as_ref(), time_format, timezone))
         .or_else(|| parse_logrus(prepared, raw_json.as_ref(), time_format, timezone))
         .or_else(|| parse_zerolog(prepared, raw_json.as_ref(), time_format, timezone))
         .or_else(|| parse_knative(prepared, raw_json.as_ref(), time_format, timezone))
+        .or_else(|| frameworks::parse_serilog(prepared, raw_json.as_ref(), time_format, timezone))
+        .or_else(|| frameworks::parse_log4j2(prepared, raw_json.as_ref(), time_format, timezone))
+        .or_else(|| frameworks::parse_logback(prepared, raw_json.as_ref(), time_format, timezone))
+        .or_else(|| {
+            frameworks::parse_python_json_logger(prepared, raw_json.as_ref(), time_format, timezone)
+        })
+        .or_else(|| frameworks::parse_structlog(prepared, raw_json.as_ref(), time_format, timezone))
         .or_else(|| parse_ecs(prepared, raw_json.as_ref(), time_format, timezone))
         .or_else(|| parse_cloud_logging(prepared, raw_json.as_ref(), time_format, timezone))
+        .or_else(|| journald::parse_journald(prepared, raw_json.as_ref(), time_format, timezone))
+        .or_else(|| syslog::parse_syslog(prepared, time_format, timezone))
+        .or_else(|| klog::parse_klog(prepared, time_format, timezone))
+        .or_else(|| access::parse_combined(prepared, time_format, timezone))
+        .or_else(|| access::parse_envoy_text(prepared, time_format, timezone))
 }
 
 fn parse_custom_json(
@@ -191,6 +311,8 @@ fn parse_custom_json(
             .map(ToOwned::to_owned),
         raw_json: Some(raw_json),
         kail_prefix: prepared.kail_prefix.clone(),
+        source: prepared.source.clone(),
+        access: None,
     })
 }
 
@@ -228,6 +350,8 @@ fn parse_pac(
             .map(ToOwned::to_owned),
         raw_json,
         kail_prefix: prepared.kail_prefix.clone(),
+        source: prepared.source.clone(),
+        access: None,
     })
 }
 
@@ -245,16 +369,19 @@ fn parse_caddy(
         let (display, parsed) = timestamp_details(value, time_format, timezone);
         (Some(display), parsed)
     });
+    let mut message = format!("{} {} -> ", caddy.request.method, caddy.request.uri);
+    let status = caddy.status.to_string();
+    let status_span = TextSpan::appended(RecordPart::Message, &message, &status);
+    message.push_str(&status);
+    message.push_str(" (");
+    let latency = format!("{}ms", (caddy.duration * 1000.0).round() as i64);
+    let latency_span = TextSpan::appended(RecordPart::Message, &message, &latency);
+    message.push_str(&latency);
+    message.push(')');
 
     Some(StructuredLog {
         level: caddy.level.to_uppercase(),
-        message: format!(
-            "{} {} -> {} ({}ms)",
-            caddy.request.method,
-            caddy.request.uri,
-            caddy.status,
-            (caddy.duration * 1000.0).round() as i64
-        ),
+        message,
         timestamp,
         parsed_timestamp,
         others: None,
@@ -275,6 +402,13 @@ fn parse_caddy(
             .map(ToOwned::to_owned),
         raw_json: raw_json.cloned(),
         kail_prefix: prepared.kail_prefix.clone(),
+        source: prepared.source.clone(),
+        access: Some(AccessInfo {
+            status: caddy.status,
+            duration: Duration::try_from_secs_f64(caddy.duration).ok(),
+            status_span: Some(status_span),
+            latency_span: Some(latency_span),
+        }),
     })
 }
 
@@ -310,6 +444,8 @@ fn parse_knative(
             .map(ToOwned::to_owned),
         raw_json: raw_json.cloned(),
         kail_prefix: prepared.kail_prefix.clone(),
+        source: prepared.source.clone(),
+        access: None,
     })
 }
 
@@ -483,6 +619,8 @@ fn build_structured_log(
         stacktrace: fields.stacktrace,
         raw_json: Some(raw_json),
         kail_prefix: prepared.kail_prefix.clone(),
+        source: prepared.source.clone(),
+        access: None,
     }
 }
 
@@ -521,32 +659,53 @@ fn timestamp_details(
     )
 }
 
+/// Map a syslog severity (0 emergency to 7 debug) to a snazy level, the
+/// emergency, alert and critical severities are grouped as critical like the
+/// cloud logging ones.
+fn syslog_severity_level(severity: u8) -> &'static str {
+    match severity {
+        0..=2 => "CRITICAL",
+        3 => "ERROR",
+        4 => "WARNING",
+        5 => "NOTICE",
+        6 => "INFO",
+        _ => "DEBUG",
+    }
+}
+
 fn normalize_cloud_logging_level(level: &str) -> &str {
     match level.to_uppercase().as_str() {
         "DEBUG" => "DEBUG",
+        "NOTICE" => "NOTICE",
         "WARNING" | "WARN" => "WARNING",
-        "ERROR" | "CRITICAL" | "ALERT" | "EMERGENCY" => "ERROR",
+        "ERROR" => "ERROR",
+        "CRITICAL" | "ALERT" | "EMERGENCY" => "CRITICAL",
         _ => "INFO",
     }
 }
 
-pub fn parse_kail_lines(config: &Config, rawline: &str) -> Option<String> {
-    let reg = Regex::new(KAIL_RE).ok()?;
-    if !reg.is_match(rawline) {
-        return None;
-    }
+/// Extract the namespace, pod and container of a kail multiplexed line.
+pub fn parse_kail_source(rawline: &str) -> Option<KailSource> {
+    let capture = KAIL_RE.captures(rawline)?;
+    Some(KailSource {
+        namespace: capture.name("namespace")?.as_str().to_string(),
+        pod: capture.name("pod")?.as_str().to_string(),
+        container: capture.name("container")?.as_str().to_string(),
+    })
+}
+
+/// Return the payload of a kail multiplexed line without its prefix.
+pub fn strip_kail_prefix(rawline: &str) -> String {
+    KAIL_RE.replace_all(rawline, "$line").to_string()
+}
 
-    let mut kail_msg_prefix = config.kail_prefix_format.clone();
-    let capture = reg.captures(rawline)?;
-    let namespace = capture.name("namespace")?.as_str();
-    let pod = capture.name("pod")?.as_str();
-    let container = capture.name("container")?.as_str();
-    kail_msg_prefix = kail_msg_prefix
-        .replace("{namespace}", namespace)
-        .replace("{pod}", pod)
-        .replace("{container}", container)
-        .replace("\\n", "\n");
-    Some(kail_msg_prefix)
+fn format_kail_prefix(config: &Config, source: &KailSource) -> String {
+    config
+        .kail_prefix_format
+        .replace("{namespace}", &source.namespace)
+        .replace("{pod}", &source.pod)
+        .replace("{container}", &source.container)
+        .replace("\\n", "\n")
 }
 
 pub fn is_kubectl_events_header(line: &str, state: &mut ParseState) -> bool {
@@ -670,11 +829,11 @@ mod tests {
     }
 
     #[test]
-    fn normalizes_cloud_logging_critical_to_error() {
+    fn keeps_cloud_logging_critical_level() {
         let line = r#"{"severity":"CRITICAL","textPayload":"cloud log","timestamp":"2022-04-25T14:20:32.505637358Z"}"#;
         let prepared = prepare_line(&Config::default(), line);
         let log = parse_structured_log(&Config::default(), &prepared).unwrap();
-        assert_eq!(log.level, "ERROR");
+        assert_eq!(log.level, "CRITICAL");
     }
 
     #[test]
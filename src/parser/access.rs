@@ -0,0 +1,393 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use regex::{Captures, Regex};
+use serde_json::{json, Map, Value};
+
+use super::{PreparedLine, StructuredFields};
+use crate::model::{AccessInfo, RecordPart, StructuredLog, TextSpan};
+
+/// Apache/nginx combined log format, the common log format is accepted too
+/// and nginx `key=value` extensions (`rt=`, `upstream=`) are picked up.
+static COMBINED_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"^(?P<client>\S+) \S+ (?P<user>\S+) \[(?P<ts>[^\]]+)\] "(?P<method>[A-Z]+) (?P<uri>\S+)(?: (?P<protocol>[^"]*))?" (?P<status>\d{3}) (?P<bytes>\d+|-)(?: "(?P<referer>[^"]*)" "(?P<agent>[^"]*)")?(?P<rest>.*)$"#,
+    )
+    .expect("valid combined log regexp")
+});
+
+/// Envoy default text access log format, with or without the response code
+/// details fields added in newer releases.
+static ENVOY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"^\[(?P<ts>[^\]]+)\] "(?P<method>[A-Z]+) (?P<uri>\S+) (?P<protocol>[^"]+)" (?P<status>\d{3}) (?P<flags>\S+) (?:\S+ \S+ "[^"]*" )?(?P<rx>\d+) (?P<tx>\d+) (?P<duration>\d+) (?P<upstream_time>\S+) "(?P<xff>[^"]*)" "(?P<agent>[^"]*)" "(?P<request_id>[^"]*)" "(?P<authority>[^"]*)" "(?P<upstream>[^"]*)""#,
+    )
+    .expect("valid envoy regexp")
+});
+
+static NGINX_KV_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?P<key>[a-z_]+)=(?:"(?P<quoted>[^"]*)"|(?P<bare>\S+))"#)
+        .expect("valid key=value regexp")
+});
+
+struct AccessRequest<'a> {
+    method: &'a str,
+    uri: &'a str,
+    status: u16,
+    latency_ms: Option<f64>,
+    upstream: Option<&'a str>,
+    timestamp: Option<(String, Option<DateTime<Utc>>)>,
+}
+
+/// Apache/nginx combined (or common) log format.
+pub(super) fn parse_combined(
+    prepared: &PreparedLine,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let captures = COMBINED_RE.captures(&prepared.line)?;
+    let mut raw_json = Map::new();
+    for name in [
+        "client", "user", "method", "uri", "protocol", "bytes", "referer", "agent",
+    ] {
+        insert_capture(&mut raw_json, &captures, name);
+    }
+    raw_json.insert(
+        "status".to_string(),
+        json!(captures["status"].parse::<u16>().ok()?),
+    );
+    for kv in NGINX_KV_RE.captures_iter(&captures["rest"]) {
+        let value = kv
+            .name("quoted")
+            .or_else(|| kv.name("bare"))
+            .map_or("", |value| value.as_str());
+        raw_json.insert(kv["key"].to_string(), Value::String(value.to_string()));
+    }
+
+    let latency_ms = ["rt", "request_time"].iter().find_map(|key| {
+        raw_json
+            .get(*key)
+            .and_then(Value::as_str)
+            .and_then(|seconds| seconds.parse::<f64>().ok())
+            .map(|seconds| seconds * 1000.0)
+    });
+    let upstream = ["upstream", "upstream_addr", "ua"]
+        .iter()
+        .find_map(|key| raw_json.get(*key).and_then(Value::as_str))
+        .map(ToOwned::to_owned);
+    let timestamp = DateTime::parse_from_str(&captures["ts"], "%d/%b/%Y:%H:%M:%S %z")
+        .ok()
+        .map(|parsed| parsed.with_timezone(&Utc));
+
+    let request = AccessRequest {
+        method: &captures["method"],
+        uri: &captures["uri"],
+        status: captures["status"].parse().ok()?,
+        latency_ms,
+        upstream: upstream.as_deref(),
+        timestamp: Some(display_timestamp(
+            &captures["ts"],
+            timestamp,
+            time_format,
+            timezone,
+        )),
+    };
+    Some(build_access_log(
+        prepared,
+        Value::Object(raw_json),
+        &request,
+        &[
+            "/method",
+            "/uri",
+            "/status",
+            "/rt",
+            "/request_time",
+            "/upstream",
+            "/upstream_addr",
+            "/ua",
+        ],
+    ))
+}
+
+/// Envoy default text access log format.
+pub(super) fn parse_envoy_text(
+    prepared: &PreparedLine,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let captures = ENVOY_RE.captures(&prepared.line)?;
+    let mut raw_json = Map::new();
+    for name in [
+        "method",
+        "uri",
+        "protocol",
+        "flags",
+        "rx",
+        "tx",
+        "duration",
+        "upstream_time",
+        "xff",
+        "agent",
+        "request_id",
+        "authority",
+        "upstream",
+    ] {
+        insert_capture(&mut raw_json, &captures, name);
+    }
+    raw_json.insert(
+        "status".to_string(),
+        json!(captures["status"].parse::<u16>().ok()?),
+    );
+
+    let request = AccessRequest {
+        method: &captures["method"],
+        uri: &captures["uri"],
+        status: captures["status"].parse().ok()?,
+        latency_ms: captures["duration"].parse().ok(),
+        upstream: Some(&captures["upstream"]).filter(|upstream| *upstream != "-"),
+        timestamp: Some(display_timestamp(
+            &captures["ts"],
+            crate::utils::parse_timestamp_str(&captures["ts"]),
+            time_format,
+            timezone,
+        )),
+    };
+    Some(build_access_log(
+        prepared,
+        Value::Object(raw_json),
+        &request,
+        &["/method", "/uri", "/status", "/duration", "/upstream"],
+    ))
+}
+
+/// Envoy (and Istio) JSON access logs using the default field names.
+pub(super) fn parse_envoy_json(
+    prepared: &PreparedLine,
+    raw_json: Option<&Value>,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let raw_json = raw_json?;
+    let status = raw_json.get("response_code").and_then(json_u16)?;
+    let request = AccessRequest {
+        method: super::json_string(raw_json, &["/method"])?,
+        uri: super::json_string(raw_json, &["/path"])?,
+        status,
+        latency_ms: raw_json.get("duration").and_then(json_f64),
+        upstream: super::json_string(raw_json, &["/upstream_host"]).filter(|host| *host != "-"),
+        timestamp: super::json_timestamp(raw_json, &["/start_time"], time_format, timezone),
+    };
+    Some(build_access_log(
+        prepared,
+        raw_json.clone(),
+        &request,
+        &[
+            "/method",
+            "/path",
+            "/response_code",
+            "/duration",
+            "/upstream_host",
+            "/start_time",
+        ],
+    ))
+}
+
+/// Traefik JSON access logs, the `Duration` is in nanoseconds.
+pub(super) fn parse_traefik(
+    prepared: &PreparedLine,
+    raw_json: Option<&Value>,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let raw_json = raw_json?;
+    let status = raw_json.get("DownstreamStatus").and_then(json_u16)?;
+    let request = AccessRequest {
+        method: super::json_string(raw_json, &["/RequestMethod"])?,
+        uri: super::json_string(raw_json, &["/RequestPath"])?,
+        status,
+        latency_ms: raw_json
+            .get("Duration")
+            .and_then(json_f64)
+            .map(|nanos| nanos / 1_000_000.0),
+        upstream: super::json_string(raw_json, &["/ServiceURL", "/ServiceAddr"]),
+        timestamp: super::json_timestamp(
+            raw_json,
+            &["/StartUTC", "/StartLocal", "/time"],
+            time_format,
+            timezone,
+        ),
+    };
+    Some(build_access_log(
+        prepared,
+        raw_json.clone(),
+        &request,
+        &[
+            "/RequestMethod",
+            "/RequestPath",
+            "/DownstreamStatus",
+            "/Duration",
+            "/ServiceURL",
+            "/ServiceAddr",
+            "/StartUTC",
+            "/StartLocal",
+            "/time",
+            "/level",
+            "/msg",
+        ],
+    ))
+}
+
+/// Render an access log like the caddy one, the level comes from the status
+/// class and the latency and upstream are shown in `others`.
+fn build_access_log(
+    prepared: &PreparedLine,
+    raw_json: Value,
+    request: &AccessRequest<'_>,
+    consumed_fields: &[&str],
+) -> StructuredLog {
+    let mut message = format!("{} {} -> ", request.method, request.uri);
+    let status = request.status.to_string();
+    let status_span = TextSpan::appended(RecordPart::Message, &message, &status);
+    message.push_str(&status);
+    let duration = request
+        .latency_ms
+        .and_then(|latency| Duration::try_from_secs_f64(latency / 1000.0).ok());
+    let latency = duration.map(crate::utils::format_latency);
+    let latency_span = latency
+        .as_deref()
+        .map(|latency| TextSpan::appended(RecordPart::Others, "", latency));
+    let others = match (latency, request.upstream) {
+        (Some(latency), Some(upstream)) => Some(format!("{latency} {upstream} ")),
+        (Some(latency), None) => Some(format!("{latency} ")),
+        (None, Some(upstream)) => Some(format!("{upstream} ")),
+        (None, None) => None,
+    };
+
+    let mut log = super::build_structured_log(
+        prepared,
+        raw_json,
+        StructuredFields {
+            level: status_level(request.status),
+            message: &message,
+            timestamp: request
+                .timestamp
+                .as_ref()
+                .map(|(display, _)| display.clone()),
+            parsed_timestamp: request.timestamp.as_ref().and_then(|(_, parsed)| *parsed),
+            others,
+            stacktrace: None,
+            consumed_fields: consumed_fields.iter().map(ToString::to_string).collect(),
+        },
+    );
+    log.access = Some(AccessInfo {
+        status: request.status,
+        duration,
+        status_span: Some(status_span),
+        latency_span,
+    });
+    log
+}
+
+fn status_level(status: u16) -> &'static str {
+    match status {
+        500.. => "ERROR",
+        400..=499 => "WARNING",
+        _ => "INFO",
+    }
+}
+
+fn display_timestamp(
+    raw: &str,
+    parsed: Option<DateTime<Utc>>,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> (String, Option<DateTime<Utc>>) {
+    let display = parsed.as_ref().map_or_else(
+        || raw.to_string(),
+        |timestamp| crate::utils::format_timestamp(timestamp, time_format, timezone),
+    );
+    (display, parsed)
+}
+
+fn insert_capture(map: &mut Map<String, Value>, captures: &Captures, name: &str) {
+    if let Some(value) = captures.name(name) {
+        if value.as_str() != "-" {
+            map.insert(name.to_string(), Value::String(value.as_str().to_string()));
+        }
+    }
+}
+
+fn json_u16(value: &Value) -> Option<u16> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .and_then(|number| u16::try_from(number).ok()),
+        Value::String(number) => number.parse().ok(),
+        _ => None,
+    }
+}
+
+fn json_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(number) => number.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::parser::{parse_structured_log, prepare_line};
+
+    fn parse(line: &str) -> crate::model::StructuredLog {
+        let prepared = prepare_line(&Config::default(), line);
+        parse_structured_log(&Config::default(), &prepared).unwrap()
+    }
+
+    #[test]
+    fn parses_combined_log_format() {
+        let log = parse(
+            r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /apache_pb.gif HTTP/1.0" 404 2326 "http://www.example.com/start.html" "Mozilla/4.08" rt=0.120 upstream="10.0.0.2:8080""#,
+        );
+        assert_eq!(log.message, "GET /apache_pb.gif -> 404");
+        assert_eq!(log.level, "WARNING");
+        assert_eq!(log.timestamp.as_deref(), Some("20:55:36"));
+        assert_eq!(log.others.as_deref(), Some("120ms 10.0.0.2:8080 "));
+        assert_eq!(log.raw_json.unwrap()["agent"], "Mozilla/4.08");
+    }
+
+    #[test]
+    fn parses_envoy_text_access_log() {
+        let log = parse(
+            r#"[2016-04-15T20:17:00.310Z] "POST /api/v1/locations HTTP/2" 503 UF 154 0 226 100 "10.0.35.28" "nsq2http" "cc21d9b0-cf5c-432b-8c7e-98aeb7988cd2" "locations" "tcp://10.0.2.1:80""#,
+        );
+        assert_eq!(log.message, "POST /api/v1/locations -> 503");
+        assert_eq!(log.level, "ERROR");
+        assert_eq!(log.timestamp.as_deref(), Some("20:17:00"));
+        assert_eq!(log.others.as_deref(), Some("226ms tcp://10.0.2.1:80 "));
+    }
+
+    #[test]
+    fn parses_envoy_json_access_log() {
+        let log = parse(
+            r#"{"start_time":"2022-04-25T14:20:32.505Z","method":"GET","path":"/healthz","protocol":"HTTP/1.1","response_code":200,"duration":3,"upstream_host":"10.0.0.3:8080","request_id":"abc"}"#,
+        );
+        assert_eq!(log.message, "GET /healthz -> 200");
+        assert_eq!(log.level, "INFO");
+        assert_eq!(log.timestamp.as_deref(), Some("14:20:32"));
+        assert_eq!(log.others.as_deref(), Some("3ms 10.0.0.3:8080 "));
+    }
+
+    #[test]
+    fn parses_traefik_access_log() {
+        let log = parse(
+            r#"{"RequestMethod":"GET","RequestPath":"/whoami","DownstreamStatus":502,"Duration":12500000,"ServiceURL":"http://10.42.0.7:80","StartUTC":"2022-04-25T14:20:32.505637358Z","level":"info","msg":"","time":"2022-04-25T14:20:32Z"}"#,
+        );
+        assert_eq!(log.message, "GET /whoami -> 502");
+        assert_eq!(log.level, "ERROR");
+        assert_eq!(log.timestamp.as_deref(), Some("14:20:32"));
+        assert_eq!(log.others.as_deref(), Some("13ms http://10.42.0.7:80 "));
+    }
+}
@@ -0,0 +1,235 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{ParseState, PreparedLine, StructuredFields};
+use crate::config::Config;
+use crate::model::StructuredLog;
+
+/// CRI log format as written by containerd and CRI-O under `/var/log/containers`.
+static CRI_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<time>\d{4}-\d{2}-\d{2}T\S+) (?P<stream>stdout|stderr) (?P<tag>[FP])(?::\S*)? ?(?P<log>.*)$",
+    )
+    .expect("valid CRI regexp")
+});
+
+#[derive(Deserialize, Debug)]
+struct DockerJsonFile {
+    log: String,
+    stream: String,
+    time: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ContainerWrapper {
+    stream: String,
+    time: String,
+}
+
+/// Replace a CRI or Docker json-file line by its inner payload.
+///
+/// Returns `None` while the pieces of a CRI partial line are being buffered,
+/// they are kept per kail source and stream as the containers interleave.
+pub(super) fn unwrap_container_line(
+    mut prepared: PreparedLine,
+    state: &mut ParseState,
+) -> Option<PreparedLine> {
+    if let Some(captures) = CRI_RE.captures(&prepared.line) {
+        let wrapper = ContainerWrapper {
+            stream: captures["stream"].to_string(),
+            time: captures["time"].to_string(),
+        };
+        let partial = &captures["tag"] == "P";
+        let payload = captures["log"].to_string();
+        let key = (prepared.source_key(), wrapper.stream.clone());
+
+        let mut log = state
+            .cri_partials
+            .remove(&key)
+            .map(|pending| pending.line)
+            .unwrap_or_default();
+        log.push_str(&payload);
+        prepared.wrapper = Some(wrapper);
+        prepared.line = log;
+        if partial {
+            state.cri_partials.insert(key, prepared);
+            return None;
+        }
+    } else if let Ok(docker) = serde_json::from_str::<DockerJsonFile>(&prepared.line) {
+        prepared.line = docker.log.trim_end_matches(['\n', '\r']).to_string();
+        prepared.wrapper = Some(ContainerWrapper {
+            stream: docker.stream,
+            time: docker.time,
+        });
+    }
+
+    Some(prepared)
+}
+
+/// The CRI partial lines still buffered, their final piece never came.
+pub(super) fn take_partials(state: &mut ParseState) -> Vec<PreparedLine> {
+    let mut partials: Vec<_> = state.cri_partials.drain().collect();
+    partials.sort_by(|(first, _), (second, _)| first.cmp(second));
+    partials.into_iter().map(|(_, prepared)| prepared).collect()
+}
+
+/// Use the wrapper timestamp when the inner payload does not carry its own,
+/// plain text payloads become a structured log with the level inferred from
+/// their text (INFO without one) and the stream kept as a field, as many
+/// containers write all their logs to stderr.
+pub(super) fn apply_wrapper_fallbacks(
+    config: &Config,
+    prepared: &PreparedLine,
+    log: Option<StructuredLog>,
+) -> Option<StructuredLog> {
+    let Some(wrapper) = prepared.wrapper.as_ref() else {
+        return log;
+    };
+    let time = Value::String(wrapper.time.clone());
+    let (timestamp, parsed_timestamp) = super::timestamp_details(
+        &time,
+        config.time_format.as_str(),
+        config.timezone.as_deref(),
+    );
+
+    if let Some(mut log) = log {
+        if log.timestamp.is_none() {
+            log.timestamp = Some(timestamp);
+            log.parsed_timestamp = parsed_timestamp;
+        }
+        return Some(log);
+    }
+
+    let level = super::raw_level::infer_level(&prepared.line).map_or("INFO", |found| found.level);
+    Some(super::build_structured_log(
+        prepared,
+        json!({
+            "log": prepared.line,
+            "stream": wrapper.stream,
+            "time": wrapper.time,
+        }),
+        StructuredFields {
+            level,
+            message: &prepared.line,
+            timestamp: Some(timestamp),
+            parsed_timestamp,
+            others: None,
+            stacktrace: None,
+            consumed_fields: vec!["/log".to_string(), "/time".to_string()],
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::model::ParsedLine;
+    use crate::parser::{parse_line, ParseState};
+
+    fn structured(line: &str, state: &mut ParseState) -> crate::model::StructuredLog {
+        match parse_line(&Config::default(), line, state) {
+            Some(ParsedLine::Structured(log)) => log,
+            other => panic!("expected structured log, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_cri_json_payload() {
+        let mut state = ParseState::default();
+        let log = structured(
+            r#"2024-01-01T00:00:00.1Z stdout F {"level":"warn","msg":"cri log"}"#,
+            &mut state,
+        );
+        assert_eq!(log.message, "cri log");
+        assert_eq!(log.level, "WARN");
+        assert_eq!(log.timestamp.as_deref(), Some("00:00:00"));
+    }
+
+    #[test]
+    fn reassembles_cri_partial_lines() {
+        let mut state = ParseState::default();
+        assert_eq!(
+            parse_line(
+                &Config::default(),
+                r#"2024-01-01T00:00:00.1Z stdout P {"level":"info","#,
+                &mut state
+            ),
+            None
+        );
+        let log = structured(
+            r#"2024-01-01T00:00:00.2Z stdout F "msg":"joined"}"#,
+            &mut state,
+        );
+        assert_eq!(log.message, "joined");
+        assert!(state.cri_partials.is_empty());
+    }
+
+    #[test]
+    fn cri_partial_lines_are_kept_per_source() {
+        let config = Config::default();
+        let mut state = ParseState::default();
+        for line in [
+            r#"ns/a[c]: 2024-01-01T00:00:00.1Z stdout P {"level":"info","#,
+            r#"ns/b[c]: 2024-01-01T00:00:00.1Z stdout P {"level":"warn","#,
+        ] {
+            assert_eq!(parse_line(&config, line, &mut state), None);
+        }
+        let log = structured(
+            r#"ns/a[c]: 2024-01-01T00:00:00.2Z stdout F "msg":"from a"}"#,
+            &mut state,
+        );
+        assert_eq!(
+            (log.level.as_str(), log.message.as_str()),
+            ("INFO", "from a")
+        );
+
+        let parsed = crate::parser::finish(&config, &mut state);
+        let [ParsedLine::Structured(log)] = parsed.as_slice() else {
+            panic!("expected the pending partial line, got {parsed:?}");
+        };
+        assert_eq!(log.message, r#"{"level":"warn","#);
+        assert_eq!(log.kail_prefix.as_deref(), Some("ns/b[c]"));
+        assert!(state.cri_partials.is_empty());
+    }
+
+    #[test]
+    fn plain_cri_payload_infers_its_level() {
+        let mut state = ParseState::default();
+        let log = structured(
+            "2024-01-01T10:11:12.1Z stderr F starting worker",
+            &mut state,
+        );
+        assert_eq!(log.message, "starting worker");
+        assert_eq!(log.level, "INFO");
+        assert_eq!(log.timestamp.as_deref(), Some("10:11:12"));
+        assert_eq!(log.raw_json.unwrap()["stream"], "stderr");
+
+        let log = structured(
+            "2024-01-01T10:11:12.1Z stdout F [error] upstream timed out",
+            &mut state,
+        );
+        assert_eq!(log.level, "ERROR");
+    }
+
+    #[test]
+    fn parses_docker_json_file_logs() {
+        let mut state = ParseState::default();
+        let log = structured(
+            r#"{"log":"{\"level\":\"debug\",\"msg\":\"docker\"}\n","stream":"stdout","time":"2024-01-01T03:04:05.000Z"}"#,
+            &mut state,
+        );
+        assert_eq!(log.message, "docker");
+        assert_eq!(log.level, "DEBUG");
+        assert_eq!(log.timestamp.as_deref(), Some("03:04:05"));
+
+        let log = structured(
+            r#"{"log":"listening on :8080\n","stream":"stdout","time":"2024-01-01T03:04:05.000Z"}"#,
+            &mut state,
+        );
+        assert_eq!(log.message, "listening on :8080");
+        assert_eq!(log.level, "INFO");
+    }
+}
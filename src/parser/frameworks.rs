@@ -0,0 +1,420 @@
+use std::fmt::Write as _;
+
+use chrono::{DateTime, LocalResult, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+
+use super::{PreparedLine, StructuredFields};
+use crate::model::StructuredLog;
+
+/// log4j2 `JsonLayout`, the timestamp is either an `instant` object or
+/// `timeMillis` depending on the log4j2 version.
+pub(super) fn parse_log4j2(
+    prepared: &PreparedLine,
+    raw_json: Option<&Value>,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let raw_json = raw_json?;
+    let logger = super::json_string(raw_json, &["/loggerName"])?;
+    let level = super::json_string(raw_json, &["/level"])?;
+    let message = super::json_string(raw_json, &["/message"])?;
+    let parsed_timestamp = log4j2_instant(raw_json);
+    let timestamp = parsed_timestamp
+        .as_ref()
+        .map(|timestamp| crate::utils::format_timestamp(timestamp, time_format, timezone));
+    let stacktrace = ["thrown", "thrownBy"]
+        .iter()
+        .find_map(|key| raw_json.get(*key))
+        .and_then(log4j2_thrown);
+
+    Some(super::build_structured_log(
+        prepared,
+        raw_json.clone(),
+        StructuredFields {
+            level: normalize_level(level),
+            message,
+            timestamp,
+            parsed_timestamp,
+            others: Some(format!("{} ", abbreviate_logger(logger))),
+            stacktrace,
+            consumed_fields: [
+                "/loggerName",
+                "/level",
+                "/message",
+                "/instant",
+                "/timeMillis",
+                "/thrown",
+                "/thrownBy",
+                "/loggerFqcn",
+                "/endOfBatch",
+            ]
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        },
+    ))
+}
+
+/// logstash-logback-encoder default JSON fields.
+pub(super) fn parse_logback(
+    prepared: &PreparedLine,
+    raw_json: Option<&Value>,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let raw_json = raw_json?;
+    let logger = super::json_string(raw_json, &["/logger_name"])?;
+    let level = super::json_string(raw_json, &["/level"])?;
+    let message = super::json_string(raw_json, &["/message"])?;
+    let timestamp = super::json_timestamp(raw_json, &["/@timestamp"], time_format, timezone);
+
+    Some(super::build_structured_log(
+        prepared,
+        raw_json.clone(),
+        StructuredFields {
+            level: normalize_level(level),
+            message,
+            timestamp: timestamp.as_ref().map(|(display, _)| display.clone()),
+            parsed_timestamp: timestamp.and_then(|(_, parsed)| parsed),
+            others: Some(format!("{} ", abbreviate_logger(logger))),
+            stacktrace: super::json_string(raw_json, &["/stack_trace"]).map(ToOwned::to_owned),
+            consumed_fields: [
+                "/logger_name",
+                "/level",
+                "/level_value",
+                "/message",
+                "/@timestamp",
+                "/@version",
+                "/stack_trace",
+            ]
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        },
+    ))
+}
+
+/// python-json-logger, the fields are the `logging` record attributes which
+/// were in the format string.
+pub(super) fn parse_python_json_logger(
+    prepared: &PreparedLine,
+    raw_json: Option<&Value>,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let raw_json = raw_json?;
+    let level = super::json_string(raw_json, &["/levelname"])?;
+    let message = super::json_string(raw_json, &["/message"])?;
+    let timestamp =
+        super::json_timestamp(raw_json, &["/timestamp", "/created"], time_format, timezone)
+            .or_else(|| {
+                let asctime = super::json_string(raw_json, &["/asctime"])?;
+                let parsed = NaiveDateTime::parse_from_str(asctime, "%Y-%m-%d %H:%M:%S,%3f")
+                    .ok()
+                    .map(|naive| naive.and_utc());
+                Some(display_timestamp(asctime, parsed, time_format, timezone))
+            });
+
+    Some(super::build_structured_log(
+        prepared,
+        raw_json.clone(),
+        StructuredFields {
+            level: normalize_level(level),
+            message,
+            timestamp: timestamp.as_ref().map(|(display, _)| display.clone()),
+            parsed_timestamp: timestamp.and_then(|(_, parsed)| parsed),
+            others: super::json_string(raw_json, &["/name"]).map(|name| format!("{name} ")),
+            stacktrace: super::json_string(raw_json, &["/exc_info"]).map(ToOwned::to_owned),
+            consumed_fields: [
+                "/levelname",
+                "/message",
+                "/timestamp",
+                "/created",
+                "/asctime",
+                "/name",
+                "/exc_info",
+            ]
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        },
+    ))
+}
+
+/// structlog `JSONRenderer` output, the message is the `event` key.
+pub(super) fn parse_structlog(
+    prepared: &PreparedLine,
+    raw_json: Option<&Value>,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let raw_json = raw_json?;
+    let level = super::json_string(raw_json, &["/level"])?;
+    let message = super::json_string(raw_json, &["/event"])?;
+    let timestamp = super::json_timestamp(raw_json, &["/timestamp"], time_format, timezone);
+
+    Some(super::build_structured_log(
+        prepared,
+        raw_json.clone(),
+        StructuredFields {
+            level: normalize_level(level),
+            message,
+            timestamp: timestamp.as_ref().map(|(display, _)| display.clone()),
+            parsed_timestamp: timestamp.and_then(|(_, parsed)| parsed),
+            others: super::json_string(raw_json, &["/logger"]).map(|logger| format!("{logger} ")),
+            stacktrace: super::json_string(raw_json, &["/exception"]).map(ToOwned::to_owned),
+            consumed_fields: ["/level", "/event", "/timestamp", "/logger", "/exception"]
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        },
+    ))
+}
+
+/// Serilog compact log event format (CLEF), when there is no rendered `@m`
+/// the `@mt` message template is rendered with the event properties.
+pub(super) fn parse_serilog(
+    prepared: &PreparedLine,
+    raw_json: Option<&Value>,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let raw_json = raw_json?;
+    let (timestamp, parsed_timestamp) =
+        super::json_timestamp(raw_json, &["/@t"], time_format, timezone)?;
+    let mut consumed_fields: Vec<String> = ["/@t", "/@m", "/@mt", "/@l", "/@x", "/@i", "/@r"]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let message = if let Some(message) = super::json_string(raw_json, &["/@m"]) {
+        message.to_string()
+    } else {
+        let (message, properties) =
+            render_message_template(super::json_string(raw_json, &["/@mt"])?, raw_json);
+        consumed_fields.extend(properties.iter().map(|property| format!("/{property}")));
+        message
+    };
+
+    Some(super::build_structured_log(
+        prepared,
+        raw_json.clone(),
+        StructuredFields {
+            level: normalize_level(super::json_string(raw_json, &["/@l"]).unwrap_or("Information")),
+            message: &message,
+            timestamp: Some(timestamp),
+            parsed_timestamp,
+            others: None,
+            stacktrace: super::json_string(raw_json, &["/@x"]).map(ToOwned::to_owned),
+            consumed_fields,
+        },
+    ))
+}
+
+/// Map the level names of the JVM, Python and .NET loggers to the snazy ones.
+pub(super) fn normalize_level(level: &str) -> &str {
+    match level.to_uppercase().as_str() {
+        "TRACE" | "VERBOSE" | "FINEST" | "FINER" => "TRACE",
+        "FINE" | "DEBUG" => "DEBUG",
+        "INFORMATION" | "INFO" => "INFO",
+        "WARN" | "WARNING" => "WARNING",
+        "ERROR" | "SEVERE" | "EXCEPTION" => "ERROR",
+        "CRITICAL" => "CRITICAL",
+        "FATAL" => "FATAL",
+        _ => level,
+    }
+}
+
+/// Shorten the package part of a Java logger name, `com.example.api.Handler`
+/// becomes `c.e.a.Handler`.
+fn abbreviate_logger(logger: &str) -> String {
+    match logger.rsplit_once('.') {
+        Some((packages, class)) => {
+            let mut abbreviated: String = packages
+                .split('.')
+                .filter_map(|package| package.chars().next())
+                .flat_map(|initial| [initial, '.'])
+                .collect();
+            abbreviated.push_str(class);
+            abbreviated
+        }
+        None => logger.to_string(),
+    }
+}
+
+fn log4j2_instant(raw_json: &Value) -> Option<DateTime<Utc>> {
+    let timestamp = if let Some(instant) = raw_json.get("instant") {
+        let seconds = instant.get("epochSecond")?.as_i64()?;
+        let nanos = instant
+            .get("nanoOfSecond")
+            .and_then(Value::as_u64)
+            .and_then(|nanos| u32::try_from(nanos).ok())
+            .unwrap_or(0);
+        Utc.timestamp_opt(seconds, nanos)
+    } else {
+        Utc.timestamp_millis_opt(raw_json.get("timeMillis")?.as_i64()?)
+    };
+    match timestamp {
+        LocalResult::Single(timestamp) => Some(timestamp),
+        _ => None,
+    }
+}
+
+/// Rebuild a Java stack trace from the log4j2 `thrown` object.
+fn log4j2_thrown(thrown: &Value) -> Option<String> {
+    let name = thrown.get("name").and_then(Value::as_str)?;
+    let mut stacktrace = match thrown.get("message").and_then(Value::as_str) {
+        Some(message) => format!("{name}: {message}"),
+        None => name.to_string(),
+    };
+    let frames = thrown
+        .get("extendedStackTrace")
+        .or_else(|| thrown.get("stackTrace"))
+        .and_then(Value::as_array);
+    for frame in frames.into_iter().flatten() {
+        let field = |key: &str| frame.get(key).and_then(Value::as_str).unwrap_or("");
+        let _ = write!(
+            stacktrace,
+            "\nat {}.{}({}:{})",
+            field("class"),
+            field("method"),
+            field("file"),
+            frame.get("line").and_then(Value::as_i64).unwrap_or(0)
+        );
+    }
+    Some(stacktrace)
+}
+
+fn display_timestamp(
+    raw: &str,
+    parsed: Option<DateTime<Utc>>,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> (String, Option<DateTime<Utc>>) {
+    let display = parsed.as_ref().map_or_else(
+        || raw.to_string(),
+        |parsed| crate::utils::format_timestamp(parsed, time_format, timezone),
+    );
+    (display, parsed)
+}
+
+/// Render a message template like `User {UserId} logged in from {@Client}`,
+/// the `@`/`$` capturing hints and the `:format`/`,alignment` suffixes are
+/// ignored and unknown properties are kept as is. Returns the rendered message
+/// and the properties which were used.
+pub(super) fn render_message_template(template: &str, properties: &Value) -> (String, Vec<String>) {
+    let mut rendered = String::new();
+    let mut used = Vec::new();
+    let mut rest = template;
+
+    while let Some(index) = rest.find(['{', '}']) {
+        rendered.push_str(&rest[..index]);
+        let tail = &rest[index..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            rendered.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        let Some(end) = tail.find('}').filter(|_| tail.starts_with('{')) else {
+            rendered.push_str(&tail[..1]);
+            rest = &tail[1..];
+            continue;
+        };
+
+        let token = &tail[..=end];
+        let name = token[1..end]
+            .trim_start_matches(['@', '$'])
+            .split([':', ','])
+            .next()
+            .unwrap_or_default();
+        match properties.get(name) {
+            Some(Value::String(value)) => rendered.push_str(value),
+            Some(value) => rendered.push_str(&value.to_string()),
+            None => rendered.push_str(token),
+        }
+        if properties.get(name).is_some() {
+            used.push(name.to_string());
+        }
+        rest = &tail[end + 1..];
+    }
+    rendered.push_str(rest);
+    (rendered, used)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::config::Config;
+    use crate::parser::{parse_structured_log, prepare_line};
+
+    #[test]
+    fn parses_log4j2_json_layout() {
+        let line = r#"{"instant":{"epochSecond":1650896432,"nanoOfSecond":505637000},"thread":"main","level":"WARN","loggerName":"com.example.api.Handler","message":"slow query","thrown":{"name":"java.sql.SQLTimeoutException","message":"timeout","extendedStackTrace":[{"class":"com.example.db.Pool","method":"query","file":"Pool.java","line":42}]},"endOfBatch":false}"#;
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.message, "slow query");
+        assert_eq!(log.level, "WARNING");
+        assert_eq!(log.timestamp.as_deref(), Some("14:20:32"));
+        assert_eq!(log.others.as_deref(), Some("c.e.a.Handler "));
+        assert_eq!(
+            log.stacktrace.as_deref(),
+            Some(
+                "java.sql.SQLTimeoutException: timeout\nat com.example.db.Pool.query(Pool.java:42)"
+            )
+        );
+    }
+
+    #[test]
+    fn parses_logback_and_python_loggers() {
+        let line = r#"{"@timestamp":"2022-04-25T14:20:32.505+00:00","@version":"1","message":"started","logger_name":"org.acme.App","thread_name":"main","level":"INFO","level_value":20000}"#;
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.message, "started");
+        assert_eq!(log.others.as_deref(), Some("o.a.App "));
+        assert_eq!(log.timestamp.as_deref(), Some("14:20:32"));
+
+        let line = r#"{"asctime":"2022-04-25 14:20:32,505","levelname":"CRITICAL","name":"worker","message":"out of memory","exc_info":"Traceback (most recent call last):"}"#;
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.level, "CRITICAL");
+        assert_eq!(log.timestamp.as_deref(), Some("14:20:32"));
+        assert_eq!(log.others.as_deref(), Some("worker "));
+        assert!(log.stacktrace.is_some());
+
+        let line = r#"{"event":"user logged in","level":"debug","timestamp":"2022-04-25T14:20:32Z","logger":"auth","user":"bob"}"#;
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.message, "user logged in");
+        assert_eq!(log.level, "DEBUG");
+        assert_eq!(log.others.as_deref(), Some("auth "));
+    }
+
+    #[test]
+    fn parses_serilog_clef_templates() {
+        let line = r#"{"@t":"2022-04-25T14:20:32.5056373Z","@mt":"User {UserId} logged in from {@Client} in {Elapsed:0.00}ms","@l":"Warning","UserId":42,"Client":{"Ip":"10.0.0.1"},"Elapsed":12.5,"Env":"prod"}"#;
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(
+            log.message,
+            r#"User 42 logged in from {"Ip":"10.0.0.1"} in 12.5ms"#
+        );
+        assert_eq!(log.level, "WARNING");
+        assert!(log.consumed_fields.contains(&"/UserId".to_string()));
+        assert!(!log.consumed_fields.contains(&"/Env".to_string()));
+
+        let line = r#"{"@t":"2022-04-25T14:20:32Z","@m":"already rendered","@x":"System.Exception: boom"}"#;
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.message, "already rendered");
+        assert_eq!(log.level, "INFO");
+        assert_eq!(log.stacktrace.as_deref(), Some("System.Exception: boom"));
+    }
+
+    #[test]
+    fn message_template_escapes_and_unknown_properties() {
+        let (rendered, used) =
+            super::render_message_template("{{literal}} {Missing} {Name}", &json!({"Name": "x"}));
+        assert_eq!(rendered, "{literal} {Missing} x");
+        assert_eq!(used, vec!["Name".to_string()]);
+    }
+}
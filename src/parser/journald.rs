@@ -0,0 +1,241 @@
+use chrono::{LocalResult, TimeZone, Utc};
+use serde_json::{Map, Value};
+
+use super::{ParseState, PreparedLine, StructuredFields};
+use crate::config::Config;
+use crate::model::StructuredLog;
+
+pub(super) enum ExportFeed {
+    NotExport,
+    Buffered,
+    Record(Value),
+}
+
+/// Collect the `KEY=value` lines of a `journalctl -o export` stream, records
+/// start with `__CURSOR=` and end with an empty line.
+pub(super) fn feed_export_line(rawline: &str, state: &mut ParseState) -> ExportFeed {
+    if state.journal_export.is_none() {
+        if !rawline.starts_with("__CURSOR=") {
+            return ExportFeed::NotExport;
+        }
+        state.journal_export = Some(Map::new());
+    }
+
+    if rawline.is_empty() {
+        let record = state.journal_export.take().unwrap_or_default();
+        return ExportFeed::Record(Value::Object(record));
+    }
+
+    // the binary fields are read as `KEY=data` lines by the input, the data
+    // can hold new lines.
+    if let Some((key, value)) = rawline.split_once('=') {
+        if is_field_name(key) {
+            if let Some(record) = state.journal_export.as_mut() {
+                record.insert(key.to_string(), Value::String(value.to_string()));
+            }
+        }
+    }
+    ExportFeed::Buffered
+}
+
+/// Whether `key` is a journal field name, upper case letters, digits and `_`.
+pub(crate) fn is_field_name(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .bytes()
+            .all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit() || byte == b'_')
+}
+
+/// The record still collected at the end of the input, when the stream does
+/// not end with an empty line.
+pub(super) fn take_export_record(state: &mut ParseState) -> Option<Value> {
+    state
+        .journal_export
+        .take()
+        .filter(|record| !record.is_empty())
+        .map(Value::Object)
+}
+
+pub(super) fn parse_export_record(config: &Config, record: &Value) -> Option<StructuredLog> {
+    let prepared = PreparedLine {
+        line: record.to_string(),
+        kail_prefix: None,
+        source: None,
+        wrapper: None,
+    };
+    parse_journald(
+        &prepared,
+        Some(record),
+        config.time_format.as_str(),
+        config.timezone.as_deref(),
+    )
+}
+
+/// Parse a `journalctl -o json` record.
+pub(super) fn parse_journald(
+    prepared: &PreparedLine,
+    raw_json: Option<&Value>,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let raw_json = raw_json?;
+    let realtime = raw_json.get("__REALTIME_TIMESTAMP");
+    let priority = raw_json.get("PRIORITY");
+    if realtime.is_none() && priority.is_none() {
+        return None;
+    }
+    let message = journal_message(raw_json.get("MESSAGE")?)?;
+    let level = priority
+        .and_then(journal_number)
+        .and_then(|priority| u8::try_from(priority).ok())
+        .map_or("INFO", super::syslog_severity_level);
+    let parsed_timestamp = realtime.and_then(journal_number).and_then(|micros| {
+        match Utc.timestamp_micros(i64::try_from(micros).ok()?) {
+            LocalResult::Single(timestamp) => Some(timestamp),
+            _ => None,
+        }
+    });
+    let timestamp = parsed_timestamp
+        .as_ref()
+        .map(|timestamp| crate::utils::format_timestamp(timestamp, time_format, timezone));
+
+    let mut log = super::build_structured_log(
+        prepared,
+        raw_json.clone(),
+        StructuredFields {
+            level,
+            message: &message,
+            timestamp,
+            parsed_timestamp,
+            others: None,
+            stacktrace: None,
+            consumed_fields: [
+                "MESSAGE",
+                "PRIORITY",
+                "__REALTIME_TIMESTAMP",
+                "__MONOTONIC_TIMESTAMP",
+                "__CURSOR",
+                "__SEQNUM",
+                "__SEQNUM_ID",
+                "_SYSTEMD_UNIT",
+                "_SYSTEMD_USER_UNIT",
+                "SYSLOG_IDENTIFIER",
+            ]
+            .iter()
+            .map(|field| format!("/{field}"))
+            .collect(),
+        },
+    );
+    if log.kail_prefix.is_none() {
+        log.kail_prefix = super::json_string(
+            raw_json,
+            &[
+                "/_SYSTEMD_UNIT",
+                "/_SYSTEMD_USER_UNIT",
+                "/SYSLOG_IDENTIFIER",
+            ],
+        )
+        .map(ToOwned::to_owned);
+    }
+    Some(log)
+}
+
+/// Journal messages are strings, or arrays of bytes when they are not valid
+/// UTF-8 or contain control characters.
+fn journal_message(value: &Value) -> Option<String> {
+    match value {
+        Value::String(message) => Some(message.clone()),
+        Value::Array(bytes) => {
+            let bytes = bytes
+                .iter()
+                .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                .collect::<Option<Vec<u8>>>()?;
+            Some(String::from_utf8_lossy(&bytes).to_string())
+        }
+        _ => None,
+    }
+}
+
+fn journal_number(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(number) => number.parse().ok(),
+        Value::Number(number) => number.as_u64(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::model::ParsedLine;
+    use crate::parser::{parse_line, parse_structured_log, prepare_line, ParseState};
+
+    #[test]
+    fn parses_journalctl_json() {
+        let line = r#"{"__REALTIME_TIMESTAMP":"1650896432505637","PRIORITY":"4","_SYSTEMD_UNIT":"kubelet.service","SYSLOG_IDENTIFIER":"kubelet","MESSAGE":"eviction threshold reached"}"#;
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.message, "eviction threshold reached");
+        assert_eq!(log.level, "WARNING");
+        assert_eq!(log.timestamp.as_deref(), Some("14:20:32"));
+        assert_eq!(log.kail_prefix.as_deref(), Some("kubelet.service"));
+    }
+
+    #[test]
+    fn decodes_byte_array_messages() {
+        let line = r#"{"PRIORITY":"3","SYSLOG_IDENTIFIER":"app","MESSAGE":[104,105,10,116,104,101,114,101]}"#;
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.message, "hi\nthere");
+        assert_eq!(log.level, "ERROR");
+        assert_eq!(log.kail_prefix.as_deref(), Some("app"));
+    }
+
+    #[test]
+    fn parses_journalctl_export_records() {
+        let config = Config::default();
+        let mut state = ParseState::default();
+        for line in [
+            "__CURSOR=s=abc;i=1",
+            "__REALTIME_TIMESTAMP=1650896432505637",
+            "PRIORITY=7",
+            "SYSLOG_IDENTIFIER=sshd",
+            "MESSAGE=Connection closed",
+        ] {
+            assert_eq!(parse_line(&config, line, &mut state), None);
+        }
+        let Some(ParsedLine::Structured(log)) = parse_line(&config, "", &mut state) else {
+            panic!("expected structured log");
+        };
+        assert_eq!(log.message, "Connection closed");
+        assert_eq!(log.level, "DEBUG");
+        assert_eq!(log.kail_prefix.as_deref(), Some("sshd"));
+        assert!(state.journal_export.is_none());
+    }
+
+    #[test]
+    fn export_records_without_message_are_shown_raw() {
+        let config = Config::default();
+        let mut state = ParseState::default();
+        for line in ["__CURSOR=s=abc;i=2", "PRIORITY=6"] {
+            assert_eq!(parse_line(&config, line, &mut state), None);
+        }
+        let Some(ParsedLine::Raw(line, None)) = parse_line(&config, "", &mut state) else {
+            panic!("expected raw line");
+        };
+        assert_eq!(line, r#"{"PRIORITY":"6","__CURSOR":"s=abc;i=2"}"#);
+
+        for line in [
+            "__CURSOR=s=abc;i=3",
+            "PRIORITY=3",
+            "MESSAGE=no trailing empty line",
+        ] {
+            assert_eq!(parse_line(&config, line, &mut state), None);
+        }
+        let parsed = crate::parser::finish(&config, &mut state);
+        let [ParsedLine::Structured(log)] = parsed.as_slice() else {
+            panic!("expected the pending record, got {parsed:?}");
+        };
+        assert_eq!(log.message, "no trailing empty line");
+    }
+}
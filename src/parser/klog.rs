@@ -0,0 +1,304 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use super::{ParseState, PreparedLine, StructuredFields};
+use crate::model::StructuredLog;
+
+/// `Lmmdd hh:mm:ss.uuuuuu threadid file:line] msg`, the message of the
+/// structured klog calls is quoted and followed by `key=value` pairs, the
+/// multi-line values are joined to the line by `join_multiline_values`.
+static KLOG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<level>[IWEF])(?P<date>\d{4} \d{2}:\d{2}:\d{2}(?:\.\d+)?)\s+(?P<pid>\d+) (?P<source>[^\s\]]+:\d+)\] ?(?P<rest>(?s:.*))$",
+    )
+    .expect("valid klog regexp")
+});
+
+pub(super) fn parse_klog(
+    prepared: &PreparedLine,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let captures = KLOG_RE.captures(&prepared.line)?;
+    let level = match &captures["level"] {
+        "I" => "INFO",
+        "W" => "WARNING",
+        "E" => "ERROR",
+        _ => "FATAL",
+    };
+    let date = &captures["date"];
+    let parsed_timestamp = crate::utils::parse_timestamp_without_year(date, "%m%d %H:%M:%S%.f");
+    let source = &captures["source"];
+
+    let rest = &captures["rest"];
+    let (message, mut raw_json) = rest
+        .starts_with('"')
+        .then(|| parse_structured_message(rest))
+        .flatten()
+        .unwrap_or_else(|| (rest.to_string(), Map::new()));
+    // The header is only stored under the keys the key/values do not use,
+    // controller-runtime logs a `source` of its own for example.
+    let mut consumed_fields = Vec::new();
+    for (key, value) in [
+        ("message", Value::String(message.clone())),
+        ("level", Value::String(level.to_string())),
+        (
+            "pid",
+            Value::Number(captures["pid"].parse::<u64>().ok()?.into()),
+        ),
+        ("source", Value::String(source.to_string())),
+    ] {
+        if !raw_json.contains_key(key) {
+            raw_json.insert(key.to_string(), value);
+            consumed_fields.push(format!("/{key}"));
+        }
+    }
+
+    Some(super::build_structured_log(
+        prepared,
+        Value::Object(raw_json),
+        StructuredFields {
+            level,
+            message: &message,
+            timestamp: Some(parsed_timestamp.as_ref().map_or_else(
+                || date.to_string(),
+                |parsed| crate::utils::format_timestamp(parsed, time_format, timezone),
+            )),
+            parsed_timestamp,
+            others: Some(format!("{source} ")),
+            stacktrace: None,
+            consumed_fields,
+        },
+    ))
+}
+
+/// Join the lines of the multi-line `key=<` values of klog to their record,
+/// the value lines are indented with a tab and the value is closed by a ` >`
+/// line. Returns the lines ready to be parsed, a record interrupted by another
+/// line before its value is closed comes first.
+pub(super) fn join_multiline_values(
+    prepared: PreparedLine,
+    state: &mut ParseState,
+) -> Vec<PreparedLine> {
+    let key = prepared.source_key();
+    let mut ready = Vec::new();
+    let (prepared, open) = match state.klog_values.remove(&key) {
+        Some(mut pending) if prepared.line.starts_with('\t') => {
+            pending.line.push('\n');
+            pending.line.push_str(&prepared.line);
+            (pending, true)
+        }
+        Some(mut pending) if prepared.line.starts_with(" >") => {
+            pending.line.push('\n');
+            pending.line.push_str(&prepared.line);
+            let open = pending.line.ends_with("=<");
+            (pending, open)
+        }
+        pending => {
+            ready.extend(pending.map(close_value));
+            let open = prepared.line.ends_with("=<") && KLOG_RE.is_match(&prepared.line);
+            (prepared, open)
+        }
+    };
+    if open {
+        state.klog_values.insert(key, prepared);
+    } else {
+        ready.push(prepared);
+    }
+    ready
+}
+
+/// The records still waiting for the end of a multi-line value, closed.
+pub(super) fn take_pending(state: &mut ParseState) -> Vec<PreparedLine> {
+    state
+        .klog_values
+        .drain()
+        .map(|(_, prepared)| close_value(prepared))
+        .collect()
+}
+
+fn close_value(mut prepared: PreparedLine) -> PreparedLine {
+    prepared.line.push_str("\n >");
+    prepared
+}
+
+/// Split the quoted message of a structured klog line from its `key=value`
+/// pairs, `None` when the line does not follow that format.
+fn parse_structured_message(rest: &str) -> Option<(String, Map<String, Value>)> {
+    let (message, mut rest) = parse_quoted(rest)?;
+    let mut fields = Map::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Some((message, fields));
+        }
+        let (key, value) = rest.split_once('=')?;
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return None;
+        }
+        let value = if value.starts_with('"') {
+            let (value, remaining) = parse_quoted(value)?;
+            rest = remaining;
+            Value::String(value)
+        } else if let Some(lines) = value.strip_prefix("<\n") {
+            let (lines, remaining) = parse_multiline(lines)?;
+            rest = remaining;
+            Value::String(lines)
+        } else {
+            let end = value.find(char::is_whitespace).unwrap_or(value.len());
+            rest = &value[end..];
+            let bare = &value[..end];
+            match serde_json::from_str::<Value>(bare) {
+                Ok(value @ (Value::Number(_) | Value::Bool(_))) => value,
+                _ => Value::String(bare.to_string()),
+            }
+        };
+        fields.insert(key.to_string(), value);
+    }
+}
+
+/// Decode the lines of a multi-line value up to its closing ` >`, returns it
+/// with the text following it.
+fn parse_multiline(input: &str) -> Option<(String, &str)> {
+    let (lines, rest) = if let Some(rest) = input.strip_prefix(" >") {
+        ("", rest)
+    } else {
+        let end = input.find("\n >")?;
+        (&input[..end], &input[end + 3..])
+    };
+    let lines: Vec<&str> = lines
+        .split('\n')
+        .map(|line| line.strip_prefix('\t').unwrap_or(line))
+        .collect();
+    Some((lines.join("\n"), rest))
+}
+
+/// Decode a Go quoted string, returns it with the text following it.
+fn parse_quoted(input: &str) -> Option<(String, &str)> {
+    let mut decoded = String::new();
+    let mut chars = input.strip_prefix('"')?.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((decoded, &input[index + 2..])),
+            '\\' => match chars.next()?.1 {
+                'n' => decoded.push('\n'),
+                't' => decoded.push('\t'),
+                escaped => decoded.push(escaped),
+            },
+            c => decoded.push(c),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Timelike};
+    use serde_json::json;
+
+    use crate::config::Config;
+    use crate::model::ParsedLine;
+    use crate::parser::{parse_lines, parse_structured_log, prepare_line, ParseState};
+
+    fn parse(line: &str) -> crate::model::StructuredLog {
+        let prepared = prepare_line(&Config::default(), line);
+        parse_structured_log(&Config::default(), &prepared).unwrap()
+    }
+
+    #[test]
+    fn parses_klog_lines() {
+        let log = parse("E0425 14:20:32.505637   12345 controller.go:59] unable to sync: timeout");
+        assert_eq!(log.level, "ERROR");
+        assert_eq!(log.message, "unable to sync: timeout");
+        assert_eq!(log.timestamp.as_deref(), Some("14:20:32"));
+        let parsed = log.parsed_timestamp.unwrap();
+        assert_eq!((parsed.month(), parsed.day()), (4, 25));
+        assert_eq!(parsed.nanosecond(), 505_637_000);
+        assert_eq!(log.others.as_deref(), Some("controller.go:59 "));
+        assert_eq!(log.raw_json.as_ref().unwrap()["pid"], json!(12345));
+    }
+
+    #[test]
+    fn parses_structured_klog_key_values() {
+        let log = parse(
+            r#"I0425 14:20:32.505637 1 reconcile.go:12] "Reconciled \"app\"" pod="ns/app" attempt=3 ready=true err="context canceled""#,
+        );
+        assert_eq!(log.level, "INFO");
+        assert_eq!(log.message, "Reconciled \"app\"");
+        let raw_json = log.raw_json.unwrap();
+        assert_eq!(raw_json["pod"], json!("ns/app"));
+        assert_eq!(raw_json["attempt"], json!(3));
+        assert_eq!(raw_json["ready"], json!(true));
+        assert_eq!(raw_json["err"], json!("context canceled"));
+
+        let log = parse(r#"W0425 14:20:32.505637 1 a.go:1] "quoted" then = free text"#);
+        assert_eq!(log.message, r#""quoted" then = free text"#);
+    }
+
+    #[test]
+    fn key_values_keep_the_header_names() {
+        let log = parse(
+            r#"I0425 14:20:32.505637 1 controller.go:173] "Starting EventSource" source="kind source: *v1.Pod""#,
+        );
+        assert_eq!(log.others.as_deref(), Some("controller.go:173 "));
+        let raw_json = log.raw_json.as_ref().unwrap();
+        assert_eq!(raw_json["source"], json!("kind source: *v1.Pod"));
+        assert_eq!(raw_json["pid"], json!(1));
+        assert!(!log.consumed_fields.contains(&"/source".to_string()));
+    }
+
+    fn structured(parsed: Vec<ParsedLine>) -> Vec<crate::model::StructuredLog> {
+        parsed
+            .into_iter()
+            .map(|parsed| match parsed {
+                ParsedLine::Structured(log) => log,
+                other => panic!("expected structured log, got {other:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn joins_multiline_values() {
+        let config = Config::default();
+        let mut state = ParseState::default();
+        for line in [
+            r#"I0425 14:20:32.505637 1 main.go:5] "Config" data=<"#,
+            "\tfirst: 1",
+            "\t  second: 2",
+        ] {
+            assert!(parse_lines(&config, line, &mut state).is_empty());
+        }
+        let logs = structured(parse_lines(&config, " > attempt=2", &mut state));
+        let raw_json = logs[0].raw_json.as_ref().unwrap();
+        assert_eq!(logs[0].message, "Config");
+        assert_eq!(raw_json["data"], json!("first: 1\n  second: 2"));
+        assert_eq!(raw_json["attempt"], json!(2));
+        assert!(state.klog_values.is_empty());
+    }
+
+    #[test]
+    fn unclosed_multiline_values_are_flushed() {
+        let config = Config::default();
+        let mut state = ParseState::default();
+        let header = r#"E0425 14:20:32.505637 1 main.go:5] "Failed" err=<"#;
+        assert!(parse_lines(&config, header, &mut state).is_empty());
+        let logs = structured(parse_lines(
+            &config,
+            "I0425 14:20:33.000000 1 main.go:6] next",
+            &mut state,
+        ));
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].message, "Failed");
+        assert_eq!(logs[0].raw_json.as_ref().unwrap()["err"], json!(""));
+        assert_eq!(logs[1].message, "next");
+
+        assert!(parse_lines(&config, header, &mut state).is_empty());
+        assert!(parse_lines(&config, "\tboom", &mut state).is_empty());
+        let logs = structured(crate::parser::finish(&config, &mut state));
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].raw_json.as_ref().unwrap()["err"], json!("boom"));
+        assert!(crate::parser::finish(&config, &mut state).is_empty());
+    }
+}
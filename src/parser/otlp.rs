@@ -0,0 +1,256 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::{Map, Value};
+
+use super::{PreparedLine, StructuredFields};
+use crate::config::Config;
+use crate::model::{KailSource, StructuredLog};
+
+/// Flatten an OTLP JSON `resourceLogs` batch, as written by the OpenTelemetry
+/// collector file exporter, into one structured log per log record.
+pub(super) fn parse_otlp_batch(
+    config: &Config,
+    prepared: &PreparedLine,
+) -> Option<Vec<StructuredLog>> {
+    if !prepared.line.contains("\"resourceLogs\"") {
+        return None;
+    }
+    let batch = serde_json::from_str::<Value>(&prepared.line).ok()?;
+    let resource_logs = batch.get("resourceLogs")?.as_array()?;
+
+    let mut logs = Vec::new();
+    for resource_log in resource_logs {
+        let resource = resource_log
+            .pointer("/resource/attributes")
+            .map_or_else(Map::new, attributes_to_map);
+        let source = resource_source(&resource);
+        for scope_log in array(resource_log, "scopeLogs") {
+            let scope = scope_log.get("scope").cloned().unwrap_or(Value::Null);
+            for record in array(scope_log, "logRecords") {
+                let mut log = parse_record(config, prepared, record, &resource, &scope);
+                if let Some(source) = source.as_ref() {
+                    log.kail_prefix = Some(super::format_kail_prefix(config, source));
+                    log.source = Some(source.clone());
+                } else if log.kail_prefix.is_none() {
+                    log.kail_prefix = resource
+                        .get("service.name")
+                        .and_then(Value::as_str)
+                        .map(ToOwned::to_owned);
+                }
+                logs.push(log);
+            }
+        }
+    }
+    Some(logs)
+}
+
+fn parse_record(
+    config: &Config,
+    prepared: &PreparedLine,
+    record: &Value,
+    resource: &Map<String, Value>,
+    scope: &Value,
+) -> StructuredLog {
+    let attributes = record
+        .get("attributes")
+        .map_or_else(Map::new, attributes_to_map);
+    let message = match record.get("body").map(any_value) {
+        Some(Value::String(body)) => body,
+        Some(Value::Null) | None => String::new(),
+        Some(body) => body.to_string(),
+    };
+    let severity_text = record.get("severityText").and_then(Value::as_str);
+    let level = match record.get("severityNumber").and_then(Value::as_u64) {
+        Some(severity) if severity > 0 => severity_number_level(severity),
+        _ => severity_text.map_or("INFO", super::frameworks::normalize_level),
+    };
+    let parsed_timestamp = ["timeUnixNano", "observedTimeUnixNano"]
+        .iter()
+        .filter_map(|key| record.get(*key).and_then(unix_nanos))
+        .find(|timestamp| timestamp.timestamp_nanos_opt() != Some(0));
+    let timestamp = parsed_timestamp.as_ref().map(|timestamp| {
+        crate::utils::format_timestamp(
+            timestamp,
+            config.time_format.as_str(),
+            config.timezone.as_deref(),
+        )
+    });
+    let stacktrace = attributes
+        .get("exception.stacktrace")
+        .and_then(Value::as_str)
+        .map(ToOwned::to_owned);
+
+    let mut raw_json = Map::new();
+    for key in [
+        "timeUnixNano",
+        "observedTimeUnixNano",
+        "severityNumber",
+        "severityText",
+        "traceId",
+        "spanId",
+        "flags",
+    ] {
+        if let Some(value) = record.get(key) {
+            raw_json.insert(key.to_string(), value.clone());
+        }
+    }
+    raw_json.insert("body".to_string(), Value::String(message.clone()));
+    if !attributes.is_empty() {
+        raw_json.insert("attributes".to_string(), Value::Object(attributes));
+    }
+    raw_json.insert("resource".to_string(), Value::Object(resource.clone()));
+    if !scope.is_null() {
+        raw_json.insert("scope".to_string(), scope.clone());
+    }
+
+    super::build_structured_log(
+        prepared,
+        Value::Object(raw_json),
+        StructuredFields {
+            level,
+            message: &message,
+            timestamp,
+            parsed_timestamp,
+            others: None,
+            stacktrace,
+            consumed_fields: [
+                "/timeUnixNano",
+                "/observedTimeUnixNano",
+                "/severityNumber",
+                "/severityText",
+                "/body",
+                "/flags",
+                "/resource",
+                "/scope",
+            ]
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        },
+    )
+}
+
+/// The OTLP severity numbers are grouped by four, from TRACE (1-4) to
+/// FATAL (21-24).
+fn severity_number_level(severity: u64) -> &'static str {
+    match severity {
+        1..=4 => "TRACE",
+        5..=8 => "DEBUG",
+        9..=12 => "INFO",
+        13..=16 => "WARNING",
+        17..=20 => "ERROR",
+        _ => "FATAL",
+    }
+}
+
+/// Kubernetes resource attributes are used as the kail source so the source
+/// filters and the split output work on them too.
+fn resource_source(resource: &Map<String, Value>) -> Option<KailSource> {
+    let attribute = |key: &str| resource.get(key).and_then(Value::as_str);
+    Some(KailSource {
+        namespace: attribute("k8s.namespace.name")?.to_string(),
+        pod: attribute("k8s.pod.name")?.to_string(),
+        container: attribute("k8s.container.name")
+            .or_else(|| attribute("service.name"))
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = &'a Value> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+fn attributes_to_map(attributes: &Value) -> Map<String, Value> {
+    attributes
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|attribute| {
+            let key = attribute.get("key")?.as_str()?;
+            Some((
+                key.to_string(),
+                attribute.get("value").map_or(Value::Null, any_value),
+            ))
+        })
+        .collect()
+}
+
+/// Convert an OTLP `AnyValue`, 64 bits integers are encoded as strings in
+/// the JSON mapping.
+fn any_value(value: &Value) -> Value {
+    let Some((kind, inner)) = value.as_object().and_then(|object| object.iter().next()) else {
+        return Value::Null;
+    };
+    match kind.as_str() {
+        "intValue" => match inner {
+            Value::String(number) => number
+                .parse::<i64>()
+                .map_or_else(|_| inner.clone(), Value::from),
+            _ => inner.clone(),
+        },
+        "arrayValue" => Value::Array(array(inner, "values").map(any_value).collect()),
+        "kvlistValue" => inner.get("values").map_or_else(
+            || Value::Object(Map::new()),
+            |values| Value::Object(attributes_to_map(values)),
+        ),
+        _ => inner.clone(),
+    }
+}
+
+fn unix_nanos(value: &Value) -> Option<DateTime<Utc>> {
+    let nanos = match value {
+        Value::String(nanos) => nanos.parse::<i64>().ok()?,
+        Value::Number(nanos) => nanos.as_i64()?,
+        _ => return None,
+    };
+    Some(Utc.timestamp_nanos(nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::model::ParsedLine;
+    use crate::parser::{parse_lines, ParseState};
+
+    const BATCH: &str = r#"{"resourceLogs":[{"resource":{"attributes":[{"key":"service.name","value":{"stringValue":"checkout"}},{"key":"k8s.namespace.name","value":{"stringValue":"shop"}},{"key":"k8s.pod.name","value":{"stringValue":"checkout-7d9f"}}]},"scopeLogs":[{"scope":{"name":"otel.logger"},"logRecords":[{"timeUnixNano":"1650896432505637000","severityNumber":17,"severityText":"Error","body":{"stringValue":"payment failed"},"attributes":[{"key":"http.status_code","value":{"intValue":"502"}},{"key":"exception.stacktrace","value":{"stringValue":"at pay()"}}],"traceId":"5b8efff798038103d269b633813fc60c","spanId":"eee19b7ec3c1b174"},{"timeUnixNano":"1650896433505637000","severityText":"WARN","body":{"kvlistValue":{"values":[{"key":"retry","value":{"boolValue":true}}]}}}]}]}]}"#;
+
+    #[test]
+    fn flattens_otlp_batches() {
+        let records = parse_lines(&Config::default(), BATCH, &mut ParseState::default());
+        assert_eq!(records.len(), 2);
+
+        let ParsedLine::Structured(log) = &records[0] else {
+            panic!("expected structured log");
+        };
+        assert_eq!(log.message, "payment failed");
+        assert_eq!(log.level, "ERROR");
+        assert_eq!(log.timestamp.as_deref(), Some("14:20:32"));
+        assert_eq!(
+            log.kail_prefix.as_deref(),
+            Some("shop/checkout-7d9f[checkout]")
+        );
+        assert_eq!(log.stacktrace.as_deref(), Some("at pay()"));
+        let raw_json = log.raw_json.as_ref().unwrap();
+        assert_eq!(raw_json["traceId"], "5b8efff798038103d269b633813fc60c");
+        assert_eq!(raw_json["attributes"]["http.status_code"], 502);
+
+        let ParsedLine::Structured(log) = &records[1] else {
+            panic!("expected structured log");
+        };
+        assert_eq!(log.message, r#"{"retry":true}"#);
+        assert_eq!(log.level, "WARNING");
+    }
+
+    #[test]
+    fn severity_numbers_map_to_levels() {
+        assert_eq!(super::severity_number_level(1), "TRACE");
+        assert_eq!(super::severity_number_level(5), "DEBUG");
+        assert_eq!(super::severity_number_level(9), "INFO");
+        assert_eq!(super::severity_number_level(13), "WARNING");
+        assert_eq!(super::severity_number_level(24), "FATAL");
+    }
+}
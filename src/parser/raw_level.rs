@@ -0,0 +1,190 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::ParseState;
+use crate::model::RawLevel;
+
+/// A klog header like `E0101 12:00:00.000000 1 main.go:42]`.
+static KLOG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<level>[IWEF])\d{4} \d{2}:\d{2}:\d{2}").expect("valid klog regexp")
+});
+
+static LEVEL_KEY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)\b(?:level|lvl|severity)=["']?(?P<level>[a-z]+)"#)
+        .expect("valid level key regexp")
+});
+
+/// Upper case level words, or any case between brackets like nginx `[error]`.
+static KEYWORD_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(?P<level>TRACE|DEBUG|INFO|NOTICE|WARN|WARNING|ERR|ERROR|CRIT|CRITICAL|ALERT|EMERG|FATAL|PANIC)\b|\[(?P<bracketed>(?i:trace|debug|info|notice|warn|warning|err|error|crit|critical|alert|emerg|fatal|panic))\]",
+    )
+    .expect("valid level keyword regexp")
+});
+
+/// Infer the level of a non structured line from a klog header, a `level=`
+/// key or a level word, the trace, notice and critical levels are kept like the
+/// structured ones.
+pub(super) fn infer_level(line: &str) -> Option<RawLevel> {
+    let payload_start = super::KAIL_RE
+        .captures(line)
+        .and_then(|captures| captures.name("line"))
+        .map_or(0, |payload| payload.start());
+    if let Some(found) = KLOG_RE
+        .captures(&line[payload_start..])
+        .and_then(|captures| captures.name("level"))
+    {
+        let level = match found.as_str() {
+            "I" => "INFO",
+            "W" => "WARNING",
+            "E" => "ERROR",
+            _ => "FATAL",
+        };
+        return Some(RawLevel {
+            level,
+            start: payload_start + found.start(),
+            end: payload_start + found.end(),
+        });
+    }
+
+    LEVEL_KEY_RE
+        .captures_iter(line)
+        .filter_map(|captures| captures.name("level"))
+        .chain(KEYWORD_RE.captures_iter(line).filter_map(|captures| {
+            captures
+                .name("level")
+                .or_else(|| captures.name("bracketed"))
+        }))
+        .find_map(|found| {
+            normalize(found.as_str()).map(|level| RawLevel {
+                level,
+                start: found.start(),
+                end: found.end(),
+            })
+        })
+}
+
+/// The level of a non structured line of `source`, a continuation line
+/// without one like the frames of a traceback inherits the level of the
+/// previous raw line, any other line without a level resets it.
+pub(super) fn line_level(line: &str, source: String, state: &mut ParseState) -> Option<RawLevel> {
+    match infer_level(line) {
+        Some(found) => {
+            state.raw_levels.insert(source, found.level);
+            Some(found)
+        }
+        None if is_continuation(line) => state.raw_levels.get(&source).map(|level| RawLevel {
+            level,
+            start: 0,
+            end: 0,
+        }),
+        None => {
+            state.raw_levels.remove(&source);
+            None
+        }
+    }
+}
+
+/// Indented lines and the headers and frames of stack traces.
+fn is_continuation(line: &str) -> bool {
+    let payload = super::KAIL_RE
+        .captures(line)
+        .and_then(|captures| captures.name("line"))
+        .map_or(line, |payload| payload.as_str());
+    payload.starts_with([' ', '\t'])
+        || ["Traceback", "Caused by:", "at "]
+            .iter()
+            .any(|prefix| payload.starts_with(prefix))
+}
+
+fn normalize(word: &str) -> Option<&'static str> {
+    Some(match word.to_uppercase().as_str() {
+        "TRACE" => "TRACE",
+        "DEBUG" => "DEBUG",
+        "INFO" => "INFO",
+        "NOTICE" => "NOTICE",
+        "WARN" | "WARNING" => "WARNING",
+        "ERR" | "ERROR" => "ERROR",
+        "CRIT" | "CRITICAL" | "ALERT" | "EMERG" => "CRITICAL",
+        "FATAL" | "PANIC" => "FATAL",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::infer_level;
+    use crate::config::Config;
+    use crate::model::{ParsedLine, RawLevel};
+    use crate::parser::{parse_lines, ParseState};
+
+    fn level(line: &str) -> Option<(&'static str, &str)> {
+        infer_level(line).map(|found| (found.level, &line[found.start..found.end]))
+    }
+
+    #[test]
+    fn infers_levels_of_raw_lines() {
+        assert_eq!(
+            level("E0101 12:00:00.000000       1 main.go:42] boom"),
+            Some(("ERROR", "E"))
+        );
+        assert_eq!(
+            level("ns/pod[c]: W0101 12:00:00.000000 1 main.go:42] careful"),
+            Some(("WARNING", "W"))
+        );
+        assert_eq!(
+            level(r#"time=12:00 level=warn msg="INFO is not the level""#),
+            Some(("WARNING", "warn"))
+        );
+        assert_eq!(
+            level("2024-01-01 12:00:00 WARN disk full"),
+            Some(("WARNING", "WARN"))
+        );
+        assert_eq!(
+            level("2024/01/01 12:00:00 [error] 123#0: upstream timed out"),
+            Some(("ERROR", "error"))
+        );
+        assert_eq!(
+            level("CRITICAL: out of memory"),
+            Some(("CRITICAL", "CRITICAL"))
+        );
+        assert_eq!(level("no error here, just an Information"), None);
+        assert_eq!(level("level=verbose nothing"), None);
+    }
+
+    #[test]
+    fn raw_lines_without_level_inherit_the_previous_one() {
+        let config = Config::default();
+        let mut state = ParseState::default();
+        let mut level = |line: &str| match parse_lines(&config, line, &mut state).remove(0) {
+            ParsedLine::Raw(_, level) => level,
+            ParsedLine::Structured(_) => None,
+            other => panic!("expected a raw line, got {other:?}"),
+        };
+        let inherited = |level| {
+            Some(RawLevel {
+                level,
+                start: 0,
+                end: 0,
+            })
+        };
+
+        assert_eq!(level("plain text"), None);
+        assert_eq!(
+            level("2024-01-01 12:00:00 ERROR boom").unwrap().level,
+            "ERROR"
+        );
+        assert_eq!(
+            level("Traceback (most recent call last):"),
+            inherited("ERROR")
+        );
+        assert_eq!(level("  File \"app.py\", line 1"), inherited("ERROR"));
+        assert_eq!(level("Caused by: timeout"), inherited("ERROR"));
+        assert_eq!(level("just a plain line"), None);
+        assert_eq!(level("  indented after the reset"), None);
+        assert_eq!(level("ns/pod[c]: starting"), None);
+        assert_eq!(level(r#"{"level":"info","msg":"structured"}"#), None);
+        assert_eq!(level("after a structured record"), None);
+    }
+}
@@ -0,0 +1,260 @@
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+
+use super::{PreparedLine, StructuredFields};
+use crate::model::StructuredLog;
+
+static RFC5424_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^<(?P<pri>\d{1,3})>(?P<version>\d{1,2}) (?P<ts>\S+) (?P<host>\S+) (?P<app>\S+) (?P<procid>\S+) (?P<msgid>\S+) (?P<rest>.*)$",
+    )
+    .expect("valid RFC 5424 regexp")
+});
+
+/// RFC 3164 (BSD) lines, the PRI is optional since rsyslog does not write it
+/// to files and the timestamp can be the BSD or the RFC 3339 one. Without the
+/// PRI only the BSD timestamp is accepted, `<ts> <word> <word>: msg` is too
+/// common in plain application logs.
+static RFC3164_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:<(?P<pri>\d{1,3})>)?(?P<ts>(?P<bsd>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2})|\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}\S*) (?P<host>\S+) (?P<app>[^\s:\[]+)(?:\[(?P<procid>[^\]]*)\])?: ?(?P<msg>.*)$",
+    )
+    .expect("valid RFC 3164 regexp")
+});
+
+const FACILITIES: [&str; 24] = [
+    "kern",
+    "user",
+    "mail",
+    "daemon",
+    "auth",
+    "syslog",
+    "lpr",
+    "news",
+    "uucp",
+    "cron",
+    "authpriv",
+    "ftp",
+    "ntp",
+    "security",
+    "console",
+    "solaris-cron",
+    "local0",
+    "local1",
+    "local2",
+    "local3",
+    "local4",
+    "local5",
+    "local6",
+    "local7",
+];
+
+const SEVERITIES: [&str; 8] = [
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+pub(super) fn parse_syslog(
+    prepared: &PreparedLine,
+    time_format: &str,
+    timezone: Option<&str>,
+) -> Option<StructuredLog> {
+    let (captures, structured_data, message) =
+        if let Some(captures) = RFC5424_RE.captures(&prepared.line) {
+            let (structured_data, message) = parse_structured_data(&captures["rest"])?;
+            let message = message.trim_start_matches('\u{feff}').to_string();
+            (captures, structured_data, message)
+        } else {
+            let captures = RFC3164_RE.captures(&prepared.line).filter(|captures| {
+                captures.name("pri").is_some() || captures.name("bsd").is_some()
+            })?;
+            let message = captures["msg"].to_string();
+            (captures, Map::new(), message)
+        };
+
+    let pri = captures
+        .name("pri")
+        .and_then(|pri| pri.as_str().parse::<u8>().ok())
+        .filter(|pri| *pri < 192);
+    let severity = pri.map(|pri| pri % 8);
+    let timestamp = &captures["ts"];
+    let parsed_timestamp = crate::utils::parse_timestamp_str(timestamp)
+        .or_else(|| crate::utils::parse_timestamp_without_year(timestamp, "%b %e %H:%M:%S"));
+
+    let mut raw_json = structured_data;
+    insert_nil_value(&mut raw_json, "hostname", &captures, "host");
+    insert_nil_value(&mut raw_json, "app_name", &captures, "app");
+    insert_nil_value(&mut raw_json, "procid", &captures, "procid");
+    insert_nil_value(&mut raw_json, "msgid", &captures, "msgid");
+    if let Some(pri) = pri {
+        raw_json.insert(
+            "facility".to_string(),
+            Value::String(FACILITIES[usize::from(pri / 8)].to_string()),
+        );
+        raw_json.insert(
+            "severity".to_string(),
+            Value::String(SEVERITIES[usize::from(pri % 8)].to_string()),
+        );
+    }
+    raw_json.insert("message".to_string(), Value::String(message.clone()));
+
+    let others = match (raw_json.get("hostname"), raw_json.get("app_name")) {
+        (Some(Value::String(host)), Some(Value::String(app))) => {
+            Some(raw_json.get("procid").and_then(Value::as_str).map_or_else(
+                || format!("{host} {app} "),
+                |pid| format!("{host} {app}[{pid}] "),
+            ))
+        }
+        (Some(Value::String(host)), _) => Some(format!("{host} ")),
+        _ => None,
+    };
+
+    Some(super::build_structured_log(
+        prepared,
+        Value::Object(raw_json),
+        StructuredFields {
+            level: severity.map_or("INFO", super::syslog_severity_level),
+            message: &message,
+            timestamp: Some(parsed_timestamp.as_ref().map_or_else(
+                || timestamp.to_string(),
+                |parsed| crate::utils::format_timestamp(parsed, time_format, timezone),
+            )),
+            parsed_timestamp,
+            others,
+            stacktrace: None,
+            consumed_fields: vec![
+                "/hostname".to_string(),
+                "/app_name".to_string(),
+                "/procid".to_string(),
+                "/severity".to_string(),
+                "/message".to_string(),
+            ],
+        },
+    ))
+}
+
+/// RFC 5424 uses `-` for the fields which are not present.
+fn insert_nil_value(map: &mut Map<String, Value>, key: &str, captures: &Captures, name: &str) {
+    if let Some(value) = captures.name(name).map(|value| value.as_str()) {
+        if value != "-" && !value.is_empty() {
+            map.insert(key.to_string(), Value::String(value.to_string()));
+        }
+    }
+}
+
+/// Parse the RFC 5424 structured data elements into an object of SD-ID to
+/// parameters, returns the remaining message.
+fn parse_structured_data(input: &str) -> Option<(Map<String, Value>, &str)> {
+    let mut elements = Map::new();
+    if let Some(rest) = input.strip_prefix('-') {
+        return Some((elements, rest.strip_prefix(' ').unwrap_or(rest)));
+    }
+
+    let mut rest = input;
+    while let Some(element) = rest.strip_prefix('[') {
+        let id_end = element.find([' ', ']'])?;
+        let id = &element[..id_end];
+        let mut params = element[id_end..].trim_start_matches(' ');
+        let mut values = Map::new();
+
+        while !params.starts_with(']') {
+            let (name, value) = params.split_once("=\"")?;
+            let mut decoded = String::new();
+            let mut chars = value.char_indices();
+            let consumed = loop {
+                match chars.next()? {
+                    (_, '\\') => {
+                        let (_, escaped) = chars.next()?;
+                        if !matches!(escaped, '"' | '\\' | ']') {
+                            decoded.push('\\');
+                        }
+                        decoded.push(escaped);
+                    }
+                    (index, '"') => break index + 1,
+                    (_, c) => decoded.push(c),
+                }
+            };
+            values.insert(name.trim().to_string(), Value::String(decoded));
+            params = value[consumed..].trim_start_matches(' ');
+        }
+
+        match elements.get_mut(id) {
+            Some(Value::Object(existing)) => existing.extend(values),
+            _ => {
+                elements.insert(id.to_string(), Value::Object(values));
+            }
+        }
+        rest = &params[1..];
+    }
+
+    Some((elements, rest.strip_prefix(' ').unwrap_or(rest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::parser::{parse_structured_log, prepare_line};
+
+    #[test]
+    fn parses_rfc5424_with_structured_data() {
+        let line = r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="Appli\"cation"][meta seq="1"] An application event"#;
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.message, "An application event");
+        assert_eq!(log.level, "NOTICE");
+        assert_eq!(log.timestamp.as_deref(), Some("22:14:15"));
+        assert_eq!(
+            log.others.as_deref(),
+            Some("mymachine.example.com evntslog ")
+        );
+        let raw_json = log.raw_json.unwrap();
+        assert_eq!(raw_json["facility"], "local4");
+        assert_eq!(raw_json["severity"], "notice");
+        assert_eq!(raw_json["msgid"], "ID47");
+        assert_eq!(
+            raw_json["exampleSDID@32473"]["eventSource"],
+            "Appli\"cation"
+        );
+        assert_eq!(raw_json["meta"]["seq"], "1");
+    }
+
+    #[test]
+    fn parses_rfc3164_with_and_without_pri() {
+        let line = "<34>Oct  1 22:14:15 mymachine su[230]: 'su root' failed for lonvick";
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.message, "'su root' failed for lonvick");
+        assert_eq!(log.level, "CRITICAL");
+        assert_eq!(log.timestamp.as_deref(), Some("22:14:15"));
+        assert_eq!(log.others.as_deref(), Some("mymachine su[230] "));
+        assert_eq!(log.raw_json.unwrap()["facility"], "auth");
+
+        let line = "<6>2024-01-01T10:00:00.123456+00:00 node1 kernel: eth0 link up";
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.message, "eth0 link up");
+        assert_eq!(log.level, "INFO");
+        assert_eq!(log.timestamp.as_deref(), Some("10:00:00"));
+        assert_eq!(log.others.as_deref(), Some("node1 kernel "));
+
+        let line = "Oct  1 22:14:15 node1 kernel: eth0 link up";
+        let prepared = prepare_line(&Config::default(), line);
+        let log = parse_structured_log(&Config::default(), &prepared).unwrap();
+        assert_eq!(log.others.as_deref(), Some("node1 kernel "));
+    }
+
+    #[test]
+    fn rfc3339_lines_without_pri_are_not_syslog() {
+        let line = "2024-01-01T10:00:00Z ERROR controller: failed";
+        let prepared = prepare_line(&Config::default(), line);
+        assert!(super::parse_syslog(&prepared, "%H:%M:%S", None).is_none());
+    }
+
+    #[test]
+    fn structured_data_nil_value_keeps_message() {
+        let (elements, message) = super::parse_structured_data("- hello world").unwrap();
+        assert!(elements.is_empty());
+        assert_eq!(message, "hello world");
+    }
+}
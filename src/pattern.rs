@@ -0,0 +1,56 @@
+use regex::{Regex, RegexBuilder};
+
+/// Compile the pattern of a regexp flag, either a plain regexp or
+/// `=/pattern/flags` where the flags are `i` (case insensitive), `l` (literal
+/// text) and `w` (whole words). The leading `=` keeps the plain regexps
+/// starting with a slash, like `/var/log/`, as they are.
+pub fn compile(pattern: &str) -> Result<Regex, String> {
+    let (source, flags) = split_flags(pattern).unwrap_or((pattern, ""));
+    let mut source = if flags.contains('l') {
+        regex::escape(source)
+    } else {
+        source.to_string()
+    };
+    if flags.contains('w') {
+        source = format!(r"\b(?:{source})\b");
+    }
+    RegexBuilder::new(&source)
+        .case_insensitive(flags.contains('i'))
+        .build()
+        .map_err(|error| format!("invalid regexp '{pattern}': {error}"))
+}
+
+/// Split a `=/pattern/flags` pattern, `None` when it is a plain regexp.
+fn split_flags(pattern: &str) -> Option<(&str, &str)> {
+    let rest = pattern.strip_prefix("=/")?;
+    let end = rest.rfind('/')?;
+    let flags = &rest[end + 1..];
+    flags
+        .chars()
+        .all(|flag| matches!(flag, 'i' | 'l' | 'w'))
+        .then_some((&rest[..end], flags))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn compiles_patterns_with_flags() {
+        let matches = |pattern: &str, text: &str| super::compile(pattern).unwrap().is_match(text);
+        assert!(matches("err.r", "error"));
+        assert!(matches("=/ERROR/i", "an error"));
+        assert!(!matches("=/ERROR/", "an error"));
+        assert!(matches("=/a.b/l", "a.b"));
+        assert!(!matches("=/a.b/l", "axb"));
+        assert!(matches("=/fail/w", "it did fail."));
+        assert!(!matches("=/fail/w", "failed"));
+        assert!(matches("=/a+b/liw", "x A+B y"));
+        // Without the leading `=` the slashes are part of the regexp.
+        assert!(matches("/healthz/", "GET /healthz/ ok"));
+        assert!(!matches("/healthz/", "GET healthz ok"));
+        assert!(matches("/var/log/", "/var/log/syslog"));
+        // Not a valid set of flags, so a plain regexp.
+        assert!(matches("=/api/v1", "a=/api/v1"));
+        assert!(super::compile("[invalid").is_err());
+        assert!(super::compile("=/[invalid/i").is_err());
+    }
+}
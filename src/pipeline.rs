@@ -1,19 +1,19 @@
+use std::collections::HashSet;
 use std::process::Command;
 
 use regex::Regex;
 
 use crate::config::{self, Config};
-use crate::model::{ParsedLine, StructuredLog};
+use crate::model::{KailSource, ParsedLine, StructuredLog};
 
 pub fn maybe_run_action(config: &Config, line: &str) {
-    let (Some(action_regexp), Some(action_command)) = (
+    let (Some(action_re), Some(action_command)) = (
         config.action_regexp.as_ref(),
         config.action_command.as_ref(),
     ) else {
         return;
     };
 
-    let action_re = Regex::new(action_regexp).expect("Invalid action_regexp");
     if let Some(captures) = action_re.captures(line) {
         let regexpmatch = captures.get(0).unwrap().as_str();
         let action_command = action_command.replace("{}", regexpmatch);
@@ -35,36 +35,71 @@ pub fn maybe_run_action(config: &Config, line: &str) {
 }
 
 pub fn process_line(config: &Config, parsed: ParsedLine) -> Option<ParsedLine> {
+    if config.slow_requests.is_some() && !matches!(parsed, ParsedLine::Structured(_)) {
+        return None;
+    }
+
     match parsed {
         ParsedLine::Structured(log) => {
             process_structured_log(config, log).map(ParsedLine::Structured)
         }
-        ParsedLine::Raw(line) => Some(ParsedLine::Raw(line)),
+        ParsedLine::Raw(line, level) => {
+            (source_is_selected(config, crate::parser::parse_kail_source(&line).as_ref())
+                && level_is_selected(config, level.map(|found| found.level))
+                && !is_skipped(config, &line)
+                && is_matched(config, &[&line]))
+            .then_some(ParsedLine::Raw(line, level))
+        }
         ParsedLine::KubectlHeader => Some(ParsedLine::KubectlHeader),
-        ParsedLine::KubectlEvent(event) => Some(ParsedLine::KubectlEvent(event)),
+        ParsedLine::KubectlEvent(event) => {
+            (level_is_selected(config, Some(kubectl_event_level(&event)))
+                && !is_skipped(config, &event.message)
+                && is_matched(config, &[&event.message]))
+            .then_some(ParsedLine::KubectlEvent(event))
+        }
     }
 }
 
-fn process_structured_log(config: &Config, mut log: StructuredLog) -> Option<StructuredLog> {
-    if config
-        .skip_line_regexp
-        .iter()
-        .any(|pattern| match Regex::new(pattern) {
-            Ok(re) => re.is_match(&log.message),
-            Err(error) => {
-                eprintln!("Invalid skip_line_regexp pattern '{pattern}': {error}");
-                false
-            }
+/// The level of a kubectl event for `-f`, from its `Warning` or `Normal` type.
+fn kubectl_event_level(event: &crate::model::KubectlEvent) -> &'static str {
+    if event.type_.eq_ignore_ascii_case("warning") {
+        "WARNING"
+    } else {
+        "INFO"
+    }
+}
+
+/// Tell whether a level passes `-f`, the lines without a level only pass
+/// when no level is filtered.
+fn level_is_selected(config: &Config, level: Option<&str>) -> bool {
+    config.filter_levels.is_empty()
+        || level.is_some_and(|level| {
+            config
+                .filter_levels
+                .contains(config::level_from_str(&level.to_lowercase()))
         })
-    {
+}
+
+fn process_structured_log(config: &Config, mut log: StructuredLog) -> Option<StructuredLog> {
+    if !source_is_selected(config, log.source.as_ref()) {
         return None;
     }
 
-    if !config.filter_levels.is_empty()
-        && !config
-            .filter_levels
-            .contains(config::level_from_str(&log.level.to_lowercase()))
-    {
+    if is_skipped(config, &log.message) || !is_matched(config, &match_texts(config, &log)) {
+        return None;
+    }
+
+    if let Some(slow) = config.slow_requests {
+        if log
+            .access
+            .and_then(|access| access.duration)
+            .is_none_or(|duration| duration < slow)
+        {
+            return None;
+        }
+    }
+
+    if !level_is_selected(config, Some(&log.level)) {
         return None;
     }
 
@@ -76,6 +111,61 @@ fn process_structured_log(config: &Config, mut log: StructuredLog) -> Option<Str
     Some(log)
 }
 
+fn is_skipped(config: &Config, text: &str) -> bool {
+    config.skip_line_regexp.iter().any(|re| re.is_match(text))
+}
+
+/// Tell whether a line passes the `--match` regexps, a regexp matches when
+/// it matches any of the texts.
+fn is_matched(config: &Config, texts: &[impl AsRef<str>]) -> bool {
+    let matches = |re: &Regex| texts.iter().any(|text| re.is_match(text.as_ref()));
+    match config.match_mode {
+        config::MatchMode::Any => {
+            config.match_regexps.is_empty() || config.match_regexps.iter().any(matches)
+        }
+        config::MatchMode::All => config.match_regexps.iter().all(matches),
+    }
+}
+
+/// The message of a record and with `--match-fields` its `key=value` fields.
+fn match_texts<'a>(config: &Config, log: &'a StructuredLog) -> Vec<std::borrow::Cow<'a, str>> {
+    let mut texts = vec![std::borrow::Cow::Borrowed(log.message.as_str())];
+    if config.match_regexps.is_empty() || !config.match_fields {
+        return texts;
+    }
+    match log.raw_json.as_ref().and_then(serde_json::Value::as_object) {
+        Some(map) => texts.extend(map.iter().map(|(key, value)| {
+            std::borrow::Cow::Owned(format!("{key}={}", json_value_to_string(value)))
+        })),
+        None => texts.extend(
+            log.extra_fields
+                .iter()
+                .map(|(key, value)| std::borrow::Cow::Owned(format!("{key}={value}"))),
+        ),
+    }
+    texts
+}
+
+fn source_is_selected(config: &Config, source: Option<&KailSource>) -> bool {
+    if config.source_filters.is_empty() && config.exclude_source_filters.is_empty() {
+        return true;
+    }
+
+    let Some(source) = source.map(ToString::to_string) else {
+        return config.source_filters.is_empty();
+    };
+
+    (config.source_filters.is_empty()
+        || config
+            .source_filters
+            .iter()
+            .any(|pattern| crate::utils::glob_match(pattern, &source)))
+        && !config
+            .exclude_source_filters
+            .iter()
+            .any(|pattern| crate::utils::glob_match(pattern, &source))
+}
+
 fn collect_extra_fields(
     config: &Config,
     raw_json: Option<&serde_json::Value>,
@@ -152,15 +242,64 @@ fn json_value_to_string(value: &serde_json::Value) -> String {
     }
 }
 
-fn get_nested_value<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
-    let mut current = value;
-    for part in path.split('.') {
-        match current {
-            serde_json::Value::Object(map) => current = map.get(part)?,
-            _ => return None,
+/// Tell whether a record shares the `--follow-field` value, the value is
+/// learned from the records matching `--follow-trigger` (or the errors) when
+/// it was not given. Returns `None` when no field is followed.
+pub fn follow_field(
+    config: &Config,
+    parsed: &ParsedLine,
+    line: &str,
+    followed: &mut HashSet<String>,
+) -> Option<bool> {
+    let follow = config.follow_field.as_ref()?;
+    if let Some(value) = follow.value.as_ref() {
+        if followed.is_empty() {
+            followed.insert(value.clone());
         }
     }
-    Some(current)
+    let ParsedLine::Structured(log) = parsed else {
+        return Some(false);
+    };
+    let Some(value) = log
+        .raw_json
+        .as_ref()
+        .and_then(|raw_json| get_nested_value(raw_json, &follow.field))
+        .map(|value| match value {
+            serde_json::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        })
+    else {
+        return Some(false);
+    };
+
+    let triggered = match &config.follow_trigger {
+        Some(trigger) => trigger.is_match(line),
+        None => {
+            follow.value.is_none() && matches!(log.level.as_str(), "ERROR" | "CRITICAL" | "FATAL")
+        }
+    };
+    // only the first trigger line picks the value, the later errors of other
+    // requests are not followed
+    if triggered && followed.is_empty() {
+        followed.insert(value.clone());
+    }
+    Some(followed.contains(&value))
+}
+
+/// Resolve a dotted path, keys which contain dots themselves like the
+/// OpenTelemetry `http.status_code` attributes are matched as is first.
+pub(crate) fn get_nested_value<'a>(
+    value: &'a serde_json::Value,
+    path: &str,
+) -> Option<&'a serde_json::Value> {
+    let serde_json::Value::Object(map) = value else {
+        return None;
+    };
+    if let Some(found) = map.get(path) {
+        return Some(found);
+    }
+    let (part, rest) = path.split_once('.')?;
+    get_nested_value(map.get(part)?, rest)
 }
 
 #[cfg(test)]
@@ -173,6 +312,92 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn follow_field_learns_values_from_triggers() {
+        let config = Config {
+            follow_field: Some(crate::config::FieldFollow {
+                field: "request_id".to_string(),
+                value: None,
+                filter: true,
+            }),
+            ..Config::default()
+        };
+        let record = |level: &str, request_id: &str| {
+            crate::model::ParsedLine::Structured(StructuredLog {
+                level: level.to_string(),
+                message: "hello".to_string(),
+                timestamp: None,
+                parsed_timestamp: None,
+                others: None,
+                consumed_fields: Vec::new(),
+                extra_fields: Vec::new(),
+                stacktrace: None,
+                raw_json: Some(serde_json::json!({ "request_id": request_id })),
+                kail_prefix: None,
+                source: None,
+                access: None,
+            })
+        };
+        let mut followed = HashSet::new();
+
+        assert_eq!(
+            follow_field(&config, &record("INFO", "r1"), "", &mut followed),
+            Some(false)
+        );
+        assert_eq!(
+            follow_field(&config, &record("ERROR", "r1"), "", &mut followed),
+            Some(true)
+        );
+        assert_eq!(
+            follow_field(&config, &record("DEBUG", "r1"), "", &mut followed),
+            Some(true)
+        );
+        assert_eq!(
+            follow_field(&config, &record("INFO", "r2"), "", &mut followed),
+            Some(false)
+        );
+        assert_eq!(
+            follow_field(&config, &record("ERROR", "r2"), "", &mut followed),
+            Some(false)
+        );
+
+        let config = Config {
+            follow_trigger: Some(Regex::new("timeout").unwrap()),
+            ..config
+        };
+        let mut followed = HashSet::new();
+        assert_eq!(
+            follow_field(
+                &config,
+                &record("INFO", "r3"),
+                "upstream timeout",
+                &mut followed
+            ),
+            Some(true)
+        );
+        assert_eq!(
+            follow_field(&Config::default(), &record("INFO", "r3"), "", &mut followed),
+            None
+        );
+    }
+
+    #[test]
+    fn nested_paths_match_keys_containing_dots() {
+        let value = serde_json::json!({
+            "attributes": {"http.status_code": 502},
+            "traceId": "abc"
+        });
+        assert_eq!(
+            get_nested_value(&value, "attributes.http.status_code"),
+            Some(&serde_json::json!(502))
+        );
+        assert_eq!(
+            get_nested_value(&value, "traceId"),
+            Some(&serde_json::json!("abc"))
+        );
+        assert_eq!(get_nested_value(&value, "attributes.http"), None);
+    }
+
     #[test]
     fn include_fields_support_nested_paths() {
         let config = Config {
@@ -196,6 +421,8 @@ mod tests {
                 }
             })),
             kail_prefix: None,
+            source: None,
+            access: None,
         };
 
         let processed =
@@ -239,6 +466,8 @@ mod tests {
                 "request_id": "req-1"
             })),
             kail_prefix: None,
+            source: None,
+            access: None,
         };
 
         let processed =
@@ -282,6 +511,8 @@ mod tests {
                 "service": { "name": "api" }
             })),
             kail_prefix: None,
+            source: None,
+            access: None,
         };
 
         let processed =
@@ -297,6 +528,163 @@ mod tests {
         );
     }
 
+    #[test]
+    fn source_filters_apply_to_structured_and_raw_lines() {
+        let config = Config {
+            source_filters: vec!["*/controller-*[manager]".to_string()],
+            exclude_source_filters: vec!["kube-system/*".to_string()],
+            ..Config::default()
+        };
+        let source = |namespace: &str, pod: &str, container: &str| KailSource {
+            namespace: namespace.to_string(),
+            pod: pod.to_string(),
+            container: container.to_string(),
+        };
+
+        assert!(source_is_selected(
+            &config,
+            Some(&source("pac", "controller-123", "manager"))
+        ));
+        assert!(!source_is_selected(
+            &config,
+            Some(&source("pac", "controller-123", "proxy"))
+        ));
+        assert!(!source_is_selected(
+            &config,
+            Some(&source("kube-system", "controller-123", "manager"))
+        ));
+        assert!(!source_is_selected(&config, None));
+
+        let processed = super::process_line(
+            &config,
+            crate::model::ParsedLine::Raw(
+                "pac/controller-1[manager]: plain text".to_string(),
+                None,
+            ),
+        );
+        assert!(processed.is_some());
+        let processed = super::process_line(
+            &config,
+            crate::model::ParsedLine::Raw("pac/webhook-1[manager]: plain text".to_string(), None),
+        );
+        assert!(processed.is_none());
+    }
+
+    #[test]
+    fn match_regexps_apply_to_structured_and_raw_lines() {
+        let mut config = Config {
+            match_regexps: vec![Regex::new("timeout").unwrap(), Regex::new("db").unwrap()],
+            skip_line_regexp: vec![Regex::new("healthz").unwrap()],
+            ..Config::default()
+        };
+        let record = |message: &str| {
+            crate::model::ParsedLine::Structured(StructuredLog {
+                level: "INFO".to_string(),
+                message: message.to_string(),
+                timestamp: None,
+                parsed_timestamp: None,
+                others: None,
+                consumed_fields: Vec::new(),
+                extra_fields: Vec::new(),
+                stacktrace: None,
+                raw_json: Some(serde_json::json!({ "msg": message, "component": "db" })),
+                kail_prefix: None,
+                source: None,
+                access: None,
+            })
+        };
+        let raw = |line: &str| {
+            let mut state = crate::parser::ParseState::default();
+            crate::parser::parse_lines(&Config::default(), line, &mut state).remove(0)
+        };
+
+        assert!(super::process_line(&config, record("timeout")).is_some());
+        assert!(super::process_line(&config, record("started")).is_none());
+        assert!(super::process_line(&config, raw("db timeout")).is_some());
+        assert!(super::process_line(&config, raw("db healthz")).is_none());
+
+        config.match_mode = config::MatchMode::All;
+        assert!(super::process_line(&config, record("timeout")).is_none());
+        assert!(super::process_line(&config, raw("db timeout")).is_some());
+        assert!(super::process_line(&config, raw("timeout")).is_none());
+
+        config.match_fields = true;
+        assert!(super::process_line(&config, record("timeout")).is_some());
+    }
+
+    #[test]
+    fn level_filters_apply_to_raw_lines_and_kubectl_events() {
+        let config = Config {
+            filter_levels: vec![config::LogLevel::Warning, config::LogLevel::Error],
+            skip_line_regexp: vec![Regex::new("ignored").unwrap()],
+            ..Config::default()
+        };
+        let raw = |line: &str| {
+            let mut state = crate::parser::ParseState::default();
+            crate::parser::parse_lines(&Config::default(), line, &mut state).remove(0)
+        };
+        let event = |type_: &str, message: &str| {
+            crate::model::ParsedLine::KubectlEvent(crate::model::KubectlEvent {
+                last_seen: "1m".to_string(),
+                type_: type_.to_string(),
+                reason: "BackOff".to_string(),
+                object: "pod/app".to_string(),
+                message: message.to_string(),
+            })
+        };
+
+        assert!(
+            super::process_line(&config, raw("E0101 12:00:00.000000 1 x.go:1] boom")).is_some()
+        );
+        assert!(
+            super::process_line(&config, raw("I0101 12:00:00.000000 1 x.go:1] fine")).is_none()
+        );
+        assert!(super::process_line(&config, raw("WARN ignored")).is_none());
+        assert!(super::process_line(&config, raw("no level")).is_none());
+        assert!(super::process_line(&config, event("Warning", "back-off")).is_some());
+        assert!(super::process_line(&config, event("Normal", "pulled")).is_none());
+        assert!(super::process_line(&config, event("Warning", "ignored")).is_none());
+        assert!(super::process_line(&config, crate::model::ParsedLine::KubectlHeader).is_some());
+    }
+
+    #[test]
+    fn slow_filter_keeps_only_slow_access_logs() {
+        let config = Config {
+            slow_requests: Some(std::time::Duration::from_millis(500)),
+            ..Config::default()
+        };
+        let request = |duration: Option<u64>| {
+            crate::model::ParsedLine::Structured(StructuredLog {
+                level: "INFO".to_string(),
+                message: "GET / -> 200".to_string(),
+                timestamp: None,
+                parsed_timestamp: None,
+                others: None,
+                consumed_fields: Vec::new(),
+                extra_fields: Vec::new(),
+                stacktrace: None,
+                raw_json: None,
+                kail_prefix: None,
+                source: None,
+                access: duration.map(|millis| crate::model::AccessInfo {
+                    status: 200,
+                    duration: Some(std::time::Duration::from_millis(millis)),
+                    status_span: None,
+                    latency_span: None,
+                }),
+            })
+        };
+
+        assert!(super::process_line(&config, request(Some(750))).is_some());
+        assert!(super::process_line(&config, request(Some(20))).is_none());
+        assert!(super::process_line(&config, request(None)).is_none());
+        assert!(super::process_line(
+            &config,
+            crate::model::ParsedLine::Raw("plain".to_string(), None)
+        )
+        .is_none());
+    }
+
     #[test]
     fn action_command_is_triggered() {
         let file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
@@ -304,7 +692,7 @@ mod tests {
         file.close().expect("Failed to close temp file");
 
         let config = Config {
-            action_regexp: Some(String::from(r"HELLO\s\w+")),
+            action_regexp: Regex::new(r"HELLO\s\w+").ok(),
             action_command: Some(
                 String::from("echo \"you said {}\" > ")
                     + file_path
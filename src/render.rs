@@ -2,10 +2,15 @@ use std::fmt::Write as _;
 use std::time::Duration;
 
 use crate::config::Config;
-use crate::model::{KubectlEvent, ParsedLine, RenderedLog, StructuredLog};
+use crate::model::{
+    AccessInfo, KubectlEvent, ParsedLine, RawLevel, RecordPart, RenderedLog, StructuredLog,
+};
 use crate::parser::ParseState;
-use crate::utils::{apply_regexps, format_duration_compact, format_time_delta};
-use yansi::Paint;
+use crate::template::{Name, TemplateValue};
+use crate::theme::Theme;
+use crate::utils::{format_duration_compact, format_time_delta};
+use serde_json::Value;
+use yansi::{Paint, Style};
 
 const DELTA_WIDTH: usize = 8;
 
@@ -14,7 +19,14 @@ pub fn render_parsed_line(
     parsed: &ParsedLine,
     state: &mut ParseState,
 ) -> Vec<String> {
-    match parsed {
+    if config.output.is_delimited() {
+        return match parsed {
+            ParsedLine::Structured(log) => vec![crate::output::render_delimited_row(config, log)],
+            _ => Vec::new(),
+        };
+    }
+
+    let lines = match parsed {
         ParsedLine::Structured(log) => {
             let delta = if config.time_delta {
                 match (
@@ -35,17 +47,42 @@ pub fn render_parsed_line(
             }
             lines
         }
-        ParsedLine::Raw(line) => vec![apply_regexps(&config.regexp_colours, line.clone())],
+        ParsedLine::Raw(line, level) => vec![render_raw_line(config, line, *level)],
         ParsedLine::KubectlHeader => vec![format!(
             "{} {} {} {} {}",
-            "LAST SEEN".bold(),
-            "TYPE".bold(),
-            "REASON".bold(),
-            "OBJECT".bold(),
-            "MESSAGE".bold()
+            "LAST SEEN".paint(config.theme.header),
+            "TYPE".paint(config.theme.header),
+            "REASON".paint(config.theme.header),
+            "OBJECT".paint(config.theme.header),
+            "MESSAGE".paint(config.theme.header)
         )],
         ParsedLine::KubectlEvent(event) => vec![render_kubectl_event(config, event)],
+    };
+    if config.output == crate::config::OutputFormat::Html
+        && !matches!(parsed, ParsedLine::Structured(_))
+    {
+        return lines.iter().map(|line| crate::html::line(line)).collect();
     }
+    lines
+}
+
+/// Paint the text the level of a non structured line was inferred from with
+/// the level style, then the `-r` regexps.
+fn render_raw_line(config: &Config, line: &str, level: Option<RawLevel>) -> String {
+    let line = match level {
+        Some(found)
+            if found.start < found.end && config.coloring != crate::config::Coloring::Never =>
+        {
+            format!(
+                "{}{}{}",
+                &line[..found.start],
+                line[found.start..found.end].paint(config.theme.level(found.level)),
+                &line[found.end..]
+            )
+        }
+        _ => line.to_string(),
+    };
+    config.regexp_colours.apply(&line)
 }
 
 pub fn render_structured_log(
@@ -54,31 +91,53 @@ pub fn render_structured_log(
     delta: Option<&str>,
 ) -> RenderedLog {
     let level = match config.level_symbols {
-        crate::config::LevelSymbols::Emoji => crate::utils::level_symbols(&log.level),
-        crate::config::LevelSymbols::Text => crate::utils::color_by_level(&log.level),
+        crate::config::LevelSymbols::Symbols => config.symbol_set.symbol(&log.level).to_string(),
+        crate::config::LevelSymbols::Text => {
+            crate::utils::color_by_level(&config.theme, &config.level_labels, &log.level)
+        }
     };
-    let timestamp = log
-        .timestamp
-        .as_ref()
-        .map_or_else(String::new, |ts| ts.fixed(13).to_string());
+    let timestamp = log.timestamp.as_ref().map_or_else(String::new, |ts| {
+        ts.paint(config.theme.timestamp).to_string()
+    });
     let delta = render_delta_slot(config, log.timestamp.is_some(), delta);
     let others = log
         .others
         .as_ref()
         .map_or_else(String::new, |others| match config.coloring {
             crate::config::Coloring::Never => format!(" {others}"),
-            _ => format!(" {}", Paint::cyan(others).italic()),
+            _ => format!(
+                " {}",
+                highlight_access(
+                    config,
+                    log.access,
+                    RecordPart::Others,
+                    others,
+                    config.theme.others
+                )
+            ),
         });
 
-    let mut message = prefixed_message(config, log);
+    let mut message = match config.coloring {
+        crate::config::Coloring::Never => log.message.clone(),
+        _ => highlight_access(
+            config,
+            log.access,
+            RecordPart::Message,
+            &log.message,
+            Style::new(),
+        ),
+    };
+    message = prefixed_message(config, log, message);
     if !config.regexp_colours.is_empty() {
-        message = apply_regexps(&config.regexp_colours, message);
+        message = config.regexp_colours.apply(&message);
     }
 
-    let extras = render_extra_fields(config, &log.extra_fields);
-    if !extras.is_empty() {
-        message.push_str(&extras);
-    }
+    let fields = if config.expand_fields {
+        render_expanded_fields(config, log)
+    } else {
+        message.push_str(&render_extra_fields(config, log));
+        Vec::new()
+    };
 
     RenderedLog {
         level,
@@ -86,6 +145,7 @@ pub fn render_structured_log(
         delta,
         others,
         message,
+        fields,
         stacktrace: log.stacktrace.clone(),
     }
 }
@@ -95,15 +155,50 @@ pub fn render_structured_lines(
     log: &StructuredLog,
     delta: Option<&str>,
 ) -> Vec<String> {
+    if config.output == crate::config::OutputFormat::Table {
+        return crate::output::render_table_row(config, log);
+    }
+
     let rendered = render_structured_log(config, log, delta);
-    let mut lines = vec![format!(
-        "{} {}{} {}{}",
-        rendered.level, rendered.timestamp, rendered.delta, rendered.others, rendered.message
-    )];
+    let mut lines = vec![match config.template.as_ref() {
+        Some(template) => template
+            .render(config.coloring != crate::config::Coloring::Never, &|name| {
+                template_value(config, log, delta, name)
+            }),
+        None => format!(
+            "{} {}{} {}{}",
+            rendered.level, rendered.timestamp, rendered.delta, rendered.others, rendered.message
+        ),
+    }];
+    if let Some(style) = line_highlight(config, log) {
+        lines[0] = crate::utils::strip_ansi(&lines[0]).paint(style).to_string();
+    }
+    if config.output == crate::config::OutputFormat::Html {
+        let stacktrace: Vec<String> = rendered
+            .stacktrace
+            .as_deref()
+            .filter(|_| !config.hide_stacktrace)
+            .map(|stacktrace| {
+                stacktrace
+                    .lines()
+                    .map(|stack_line| {
+                        format_stack_line(
+                            stack_line,
+                            &config.theme,
+                            config.coloring == crate::config::Coloring::Never,
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        return crate::html::record(&lines[0], &rendered.fields, &stacktrace);
+    }
+    lines.extend(rendered.fields);
 
     if !config.hide_stacktrace {
         if let Some(stacktrace) = rendered.stacktrace.as_ref() {
             lines.extend(render_stacktrace_block(
+                &config.theme,
                 stacktrace,
                 config.coloring == crate::config::Coloring::Never,
             ));
@@ -113,6 +208,83 @@ pub fn render_structured_lines(
     lines
 }
 
+fn template_value(
+    config: &Config,
+    log: &StructuredLog,
+    delta: Option<&str>,
+    name: &Name,
+) -> Option<TemplateValue> {
+    let styled = |plain: String, style: Style| TemplateValue {
+        styled: plain.paint(style).to_string(),
+        plain,
+        style,
+    };
+    match name {
+        Name::Time => log
+            .timestamp
+            .clone()
+            .map(|timestamp| styled(timestamp, config.theme.timestamp)),
+        Name::Level => Some(match config.level_symbols {
+            crate::config::LevelSymbols::Symbols => {
+                TemplateValue::plain(config.symbol_set.symbol(&log.level).to_string())
+            }
+            crate::config::LevelSymbols::Text => styled(
+                crate::levels::label_text(&config.level_labels, &log.level),
+                config.theme.level(&log.level),
+            ),
+        }),
+        Name::Delta => delta.map(|delta| styled(delta.to_string(), config.theme.delta)),
+        Name::Prefix => log
+            .kail_prefix
+            .clone()
+            .filter(|_| config.kail_prefix != crate::config::KailPrefix::Hide)
+            .map(|prefix| styled(prefix, config.theme.prefix)),
+        Name::Others => log.others.as_deref().map(str::trim_end).map(|others| {
+            let style = config.theme.others;
+            TemplateValue {
+                plain: others.to_string(),
+                styled: highlight_access(config, log.access, RecordPart::Others, others, style),
+                style,
+            }
+        }),
+        Name::Message => {
+            let mut message = highlight_access(
+                config,
+                log.access,
+                RecordPart::Message,
+                &log.message,
+                Style::new(),
+            );
+            if !config.regexp_colours.is_empty() {
+                message = config.regexp_colours.apply(&message);
+            }
+            Some(TemplateValue {
+                plain: log.message.clone(),
+                styled: message,
+                style: Style::new(),
+            })
+        }
+        Name::Extras if config.expand_fields => None,
+        Name::Extras => {
+            let extras = render_extra_fields(config, log);
+            let extras = extras.trim_start();
+            Some(TemplateValue {
+                plain: crate::utils::strip_ansi(extras),
+                styled: extras.to_string(),
+                style: Style::new(),
+            })
+        }
+        Name::Field(path) => log
+            .raw_json
+            .as_ref()
+            .and_then(|raw_json| crate::pipeline::get_nested_value(raw_json, path))
+            .map(|value| match value {
+                Value::String(value) => TemplateValue::plain(value.clone()),
+                value => TemplateValue::plain(value.to_string()),
+            }),
+    }
+}
+
 fn render_delta_slot(config: &Config, has_timestamp: bool, delta: Option<&str>) -> String {
     if !config.time_delta || !has_timestamp {
         return String::new();
@@ -121,25 +293,29 @@ fn render_delta_slot(config: &Config, has_timestamp: bool, delta: Option<&str>)
     let padded = format!(" {:<DELTA_WIDTH$}", delta.unwrap_or(""));
     match config.coloring {
         crate::config::Coloring::Never => padded,
-        _ if delta.is_some() => Paint::new(padded).fixed(8).to_string(),
+        _ if delta.is_some() => padded.paint(config.theme.delta).to_string(),
         _ => padded,
     }
 }
 
-pub fn render_stacktrace_block(stacktrace: &str, disable_coloring: bool) -> Vec<String> {
+pub fn render_stacktrace_block(
+    theme: &Theme,
+    stacktrace: &str,
+    disable_coloring: bool,
+) -> Vec<String> {
     let mut lines = vec![
-        format!("\n{}", "─".repeat(80).fixed(8)),
-        " Stacktrace:".red().bold().to_string(),
+        format!("\n{}", "─".repeat(80).paint(theme.rule)),
+        " Stacktrace:".paint(theme.stack_header).to_string(),
     ];
 
     for stack_line in stacktrace.lines() {
         lines.push(format!(
             "   {}",
-            format_stack_line(stack_line, disable_coloring)
+            format_stack_line(stack_line, theme, disable_coloring)
         ));
     }
 
-    lines.push(format!("{}\n", "─".repeat(80).fixed(8)));
+    lines.push(format!("{}\n", "─".repeat(80).paint(theme.rule)));
     lines
 }
 
@@ -153,58 +329,138 @@ pub fn render_kubectl_event(config: &Config, event: &KubectlEvent) -> String {
     let reason_padded = format!("{:REASON_WIDTH$}", event.reason);
     let object_padded = format!("{:52}", event.object);
 
-    let type_colored = match event.type_.as_str() {
-        "Warning" => Paint::red(&type_padded).bold(),
-        "Normal" => Paint::green(&type_padded).bold(),
-        _ => Paint::new(&type_padded).bold(),
-    };
-    let reason_colored = Paint::yellow(&reason_padded).bold();
-    let object_colored = colorize_object_type(&object_padded);
-    let last_seen_colored = Paint::new(&last_seen_padded).bold();
+    let theme = &config.theme;
+    let type_colored = type_padded.paint(match event.type_.as_str() {
+        "Warning" => theme.kubectl_warning,
+        "Normal" => theme.kubectl_normal,
+        _ => theme.kubectl_other,
+    });
+    let reason_colored = reason_padded.paint(theme.kubectl_reason);
+    let object_colored = colorize_object_type(theme, &object_padded);
+    let last_seen_colored = last_seen_padded.paint(theme.kubectl_last_seen);
     let message = if config.regexp_colours.is_empty() {
         event.message.clone()
     } else {
-        apply_regexps(&config.regexp_colours, event.message.clone())
+        config.regexp_colours.apply(&event.message)
     };
 
     format!("{last_seen_colored} {type_colored} {reason_colored} {object_colored} {message}")
 }
 
-pub fn render_duplicate_summary(config: &Config, count: usize, window: Duration) -> String {
+/// Prefix the lines of a `--mark-field` matching record with a bar, the other
+/// records are indented the same way to keep them aligned.
+pub fn mark_lines(config: &Config, lines: Vec<String>, marked: bool) -> Vec<String> {
+    let prefix = match (marked, config.coloring) {
+        (false, _) => "  ".to_string(),
+        (true, crate::config::Coloring::Never) => "▌ ".to_string(),
+        (true, _) => format!("{} ", "▌".paint(config.theme.mark)),
+    };
+    if config.output == crate::config::OutputFormat::Html {
+        return lines
+            .into_iter()
+            .map(|line| crate::html::prefix_line(&line, &prefix))
+            .collect();
+    }
+    lines
+        .into_iter()
+        .map(|line| format!("{prefix}{line}"))
+        .collect()
+}
+
+pub fn render_duplicate_summary(config: &Config, count: usize, window: Duration) -> Option<String> {
+    if config.output.is_delimited() {
+        return None;
+    }
     let summary = format!("x{count} in {}", format_duration_compact(window));
     let padded = format!("{summary:>24}");
 
-    match config.coloring {
+    let summary = match config.coloring {
         crate::config::Coloring::Never => padded,
-        _ => Paint::new(padded).fixed(8).italic().to_string(),
+        _ => padded.paint(config.theme.hint).to_string(),
+    };
+    if config.output == crate::config::OutputFormat::Html {
+        return Some(crate::html::line(&summary));
     }
+    Some(summary)
 }
 
-pub fn colorize_object_type(object: &str) -> String {
-    use yansi::Color;
+pub fn render_group_header(
+    config: &Config,
+    field: &str,
+    group: &crate::group::Group,
+) -> Option<String> {
+    if config.output.is_delimited() {
+        return None;
+    }
+    let format = |timestamp: &chrono::DateTime<chrono::Utc>| {
+        crate::utils::format_timestamp(
+            timestamp,
+            config.time_format.as_str(),
+            config.timezone.as_deref(),
+        )
+    };
+    let span = match (group.first_timestamp, group.last_timestamp) {
+        (Some(first), Some(last)) if first != last => format!(
+            " {} → {} ({})",
+            format(&first),
+            format(&last),
+            format_duration_compact((last - first).to_std().unwrap_or_default())
+        ),
+        (Some(first), _) => format!(" {}", format(&first)),
+        _ => String::new(),
+    };
+    let count = match group.events.len() {
+        1 => "1 line".to_string(),
+        count => format!("{count} lines"),
+    };
+    let level = group
+        .worst_level
+        .as_deref()
+        .map_or_else(String::new, |level| {
+            let level = match level {
+                "WARNING" => "WARN",
+                level => level,
+            };
+            format!(" · {level}")
+        });
+
+    let header = match config.coloring {
+        crate::config::Coloring::Never => {
+            format!("── {field}={} ──{span} · {count}{level}", group.key)
+        }
+        _ => format!(
+            "{} {}{}{} {}{}",
+            "──".paint(config.theme.rule),
+            format!("{field}={}", group.key).paint(config.theme.header),
+            " ──".paint(config.theme.rule),
+            span.paint(config.theme.rule),
+            format!("· {count}").paint(config.theme.rule),
+            match group.worst_level.as_deref() {
+                Some(worst @ ("ERROR" | "CRITICAL" | "FATAL")) => {
+                    level.paint(config.theme.level(worst).bold()).to_string()
+                }
+                Some(worst @ "WARNING") => level.paint(config.theme.level(worst)).to_string(),
+                _ => level.paint(config.theme.rule).to_string(),
+            }
+        ),
+    };
+    if config.output == crate::config::OutputFormat::Html {
+        return Some(crate::html::line(&header));
+    }
+    Some(header)
+}
 
+pub fn colorize_object_type(theme: &Theme, object: &str) -> String {
     let (prefix, rest) = if let Some(idx) = object.find('/') {
         (&object[..idx], &object[idx..])
     } else {
         (object, "")
     };
 
-    let colored_prefix = match prefix {
-        "pod" => Paint::magenta(prefix).bold(),
-        "replicaset" => Paint::blue(prefix).bold(),
-        "deployment" => Paint::green(prefix).bold(),
-        "service" => Paint::yellow(prefix).bold(),
-        "job" => Paint::cyan(prefix).bold(),
-        "daemonset" => Paint::red(prefix).bold(),
-        "statefulset" => Paint::new(prefix).fg(Color::Fixed(93)).bold(),
-        "configmap" => Paint::new(prefix).fg(Color::Fixed(208)).bold(),
-        "secret" => Paint::new(prefix).fg(Color::Fixed(244)).bold(),
-        _ => Paint::white(prefix).bold(),
-    };
-    format!("{colored_prefix}{rest}")
+    format!("{}{rest}", prefix.paint(theme.object(prefix)))
 }
 
-pub fn format_stack_line(line: &str, disable_coloring: bool) -> String {
+pub fn format_stack_line(line: &str, theme: &Theme, disable_coloring: bool) -> String {
     if line.contains(".go:")
         || line.contains(".rs:")
         || line.contains(".js:")
@@ -226,17 +482,17 @@ pub fn format_stack_line(line: &str, disable_coloring: bool) -> String {
                     if disable_coloring {
                         path.to_string()
                     } else {
-                        path.fixed(15).to_string()
+                        path.paint(theme.stack_path).to_string()
                     },
                     if disable_coloring {
                         filename.to_string()
                     } else {
-                        filename.yellow().bold().to_string()
+                        filename.paint(theme.stack_file).to_string()
                     },
                     if disable_coloring {
                         format!(":{line_num}")
                     } else {
-                        format!(":{line_num}").green().to_string()
+                        format!(":{line_num}").paint(theme.stack_line).to_string()
                     }
                 );
             }
@@ -246,12 +502,12 @@ pub fn format_stack_line(line: &str, disable_coloring: bool) -> String {
                 if disable_coloring {
                     path.to_string()
                 } else {
-                    path.fixed(15).to_string()
+                    path.paint(theme.stack_path).to_string()
                 },
                 if disable_coloring {
                     rest.to_string()
                 } else {
-                    rest.yellow().bold().to_string()
+                    rest.paint(theme.stack_file).to_string()
                 }
             );
         }
@@ -265,12 +521,12 @@ pub fn format_stack_line(line: &str, disable_coloring: bool) -> String {
                 if disable_coloring {
                     filename.to_string()
                 } else {
-                    filename.yellow().bold().to_string()
+                    filename.paint(theme.stack_file).to_string()
                 },
                 if disable_coloring {
                     format!(":{line_num}")
                 } else {
-                    format!(":{line_num}").green().to_string()
+                    format!(":{line_num}").paint(theme.stack_line).to_string()
                 }
             );
         }
@@ -285,12 +541,12 @@ pub fn format_stack_line(line: &str, disable_coloring: bool) -> String {
             if disable_coloring {
                 package_path.to_string()
             } else {
-                package_path.fixed(15).to_string()
+                package_path.paint(theme.stack_path).to_string()
             },
             if disable_coloring {
                 func_name.to_string()
             } else {
-                func_name.cyan().bold().to_string()
+                func_name.paint(theme.stack_function).to_string()
             }
         );
     }
@@ -298,41 +554,283 @@ pub fn format_stack_line(line: &str, disable_coloring: bool) -> String {
     if disable_coloring {
         line.to_string()
     } else {
-        line.fixed(15).to_string()
+        line.paint(theme.stack_path).to_string()
     }
 }
 
-fn prefixed_message(config: &Config, log: &StructuredLog) -> String {
+fn prefixed_message(config: &Config, log: &StructuredLog, message: String) -> String {
     if log.kail_prefix.is_none() || config.kail_prefix == crate::config::KailPrefix::Hide {
-        return log.message.clone();
+        return message;
     }
 
     let prefix = log.kail_prefix.as_ref().unwrap();
     match config.coloring {
-        crate::config::Coloring::Never => format!("{prefix} {}", log.message),
-        _ => format!("{} {}", Paint::blue(prefix), log.message),
+        crate::config::Coloring::Never => format!("{prefix} {message}"),
+        _ => format!("{} {message}", prefix.paint(config.theme.prefix)),
+    }
+}
+
+/// Color the status code of an access log by class and its latency when it
+/// goes above the configured thresholds, the rest of `text` gets `base`. The
+/// spans come from the parser, `text` is the `part` of the record they were
+/// recorded in.
+fn highlight_access(
+    config: &Config,
+    access: Option<AccessInfo>,
+    part: RecordPart,
+    text: &str,
+    base: Style,
+) -> String {
+    let Some(access) = access else {
+        return text.paint(base).to_string();
+    };
+
+    let status_style = match access.status {
+        200..=299 => config.theme.status_2xx,
+        300..=399 => config.theme.status_3xx,
+        400..=499 => config.theme.status_4xx,
+        500..=599 => config.theme.status_5xx,
+        _ => base,
+    };
+    let latency_style = match access.duration {
+        Some(duration) if duration >= config.latency_critical => config.theme.latency_critical,
+        Some(duration) if duration >= config.latency_warning => config.theme.latency_warning,
+        _ => base,
+    };
+
+    let mut highlights: Vec<(usize, usize, Style)> = [
+        access.status_span.map(|span| (span, status_style)),
+        access.latency_span.map(|span| (span, latency_style)),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|(span, _)| span.part == part && text.get(span.start..span.end).is_some())
+    .map(|(span, style)| (span.start, span.end, style))
+    .collect();
+    highlights.sort_by_key(|(start, _, _)| *start);
+
+    let mut rendered = String::new();
+    let mut position = 0;
+    for (start, end, style) in highlights {
+        if start < position {
+            continue;
+        }
+        let _ = write!(
+            rendered,
+            "{}{}",
+            text[position..start].paint(base),
+            text[start..end].paint(style)
+        );
+        position = end;
+    }
+    let _ = write!(rendered, "{}", text[position..].paint(base));
+    rendered
+}
+
+/// The style of the first `--highlight-line` rule matching the record.
+pub(crate) fn line_highlight(config: &Config, log: &StructuredLog) -> Option<Style> {
+    if config.coloring == crate::config::Coloring::Never {
+        return None;
+    }
+    config
+        .line_highlights
+        .iter()
+        .find(|rule| record_field(log, &rule.field).is_some_and(|value| rule.matches(&value)))
+        .map(|rule| rule.style)
+}
+
+/// The text of a record field, looked up in the JSON payload first and in the
+/// extra fields of the other formats.
+fn record_field(log: &StructuredLog, field: &str) -> Option<String> {
+    log.raw_json
+        .as_ref()
+        .and_then(|raw_json| crate::pipeline::get_nested_value(raw_json, field))
+        .map(|value| match value {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        })
+        .or_else(|| {
+            log.extra_fields
+                .iter()
+                .find(|(key, _)| key == field)
+                .map(|(_, value)| value.clone())
+        })
+}
+
+/// The style of the first `--highlight-field` rule of the field at `path`
+/// matching its value.
+fn field_highlight(config: &Config, path: &str, value: &str) -> Option<Style> {
+    if config.coloring == crate::config::Coloring::Never {
+        return None;
     }
+    config
+        .field_highlights
+        .iter()
+        .find(|rule| rule.field == path && rule.matches(value))
+        .map(|rule| rule.style)
 }
 
-fn render_extra_fields(config: &Config, extra_fields: &[(String, String)]) -> String {
+/// The style of the first `--highlight-field` rule on a field nested in the
+/// extra field `key`, like `http.status` in `http`, matching the record.
+fn nested_field_highlight(config: &Config, log: &StructuredLog, key: &str) -> Option<Style> {
+    if config.coloring == crate::config::Coloring::Never {
+        return None;
+    }
+    config
+        .field_highlights
+        .iter()
+        .filter(|rule| {
+            rule.field
+                .strip_prefix(key)
+                .is_some_and(|nested| nested.starts_with('.'))
+        })
+        .find(|rule| record_field(log, &rule.field).is_some_and(|value| rule.matches(&value)))
+        .map(|rule| rule.style)
+}
+
+fn render_extra_fields(config: &Config, log: &StructuredLog) -> String {
     let mut rendered = String::new();
-    for (key, value) in extra_fields {
+    for (key, value) in &log.extra_fields {
         let key_str = match config.coloring {
             crate::config::Coloring::Never => key.clone(),
-            _ => Paint::new(key).bold().to_string(),
+            _ => key.paint(config.theme.field_key).to_string(),
         };
-        let _ = write!(rendered, " {key_str}={value}");
+        let style = field_highlight(config, key, value)
+            .or_else(|| nested_field_highlight(config, log, key));
+        match style {
+            Some(style) => {
+                let _ = write!(rendered, " {key_str}={}", value.paint(style));
+            }
+            None => {
+                let _ = write!(rendered, " {key_str}={value}");
+            }
+        }
     }
     rendered
 }
 
+/// Render the extra fields as an indented tree below the message from their
+/// JSON values, strings holding JSON objects or arrays are decoded.
+fn render_expanded_fields(config: &Config, log: &StructuredLog) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (key, value) in &log.extra_fields {
+        let label = field_label(config, key);
+        if let Some(style) = field_highlight(config, key, value) {
+            lines.push(format!("    {label} {}", value.paint(style)));
+            continue;
+        }
+        let value = log
+            .raw_json
+            .as_ref()
+            .and_then(|raw_json| crate::pipeline::get_nested_value(raw_json, key))
+            .map_or_else(|| Value::String(value.clone()), Clone::clone);
+        render_field_tree(config, &label, Some(key), &value, 1, &mut lines);
+    }
+    lines
+}
+
+/// Render a field and its children, `path` is the dotted path of the field
+/// for `--highlight-field`, the array items have none.
+fn render_field_tree(
+    config: &Config,
+    label: &str,
+    path: Option<&str>,
+    value: &Value,
+    depth: usize,
+    lines: &mut Vec<String>,
+) {
+    let indent = "  ".repeat(depth + 1);
+    let value = decode_json_string(value);
+    let value = value.as_ref();
+    match value {
+        Value::Object(map) if !map.is_empty() && depth <= config.expand_depth => {
+            lines.push(format!("{indent}{label}"));
+            for (key, child) in map {
+                let label = field_label(config, key);
+                let path = path.map(|path| format!("{path}.{key}"));
+                render_field_tree(config, &label, path.as_deref(), child, depth + 1, lines);
+            }
+        }
+        Value::Array(items) if !items.is_empty() && depth <= config.expand_depth => {
+            lines.push(format!("{indent}{label}"));
+            for item in items.iter().take(config.expand_array_limit) {
+                render_field_tree(config, "-", None, item, depth + 1, lines);
+            }
+            if items.len() > config.expand_array_limit {
+                let more = format!("… {} more", items.len() - config.expand_array_limit);
+                lines.push(format!("{indent}  {}", dim(config, &more)));
+            }
+        }
+        _ => {
+            let highlight = path.and_then(|path| field_highlight(config, path, &field_text(value)));
+            let rendered = highlight.map_or_else(
+                || field_value(config, value),
+                |style| field_text(value).paint(style).to_string(),
+            );
+            lines.push(format!("{indent}{label} {rendered}"));
+        }
+    }
+}
+
+fn decode_json_string(value: &Value) -> std::borrow::Cow<'_, Value> {
+    if let Value::String(inner) = value {
+        let trimmed = inner.trim_start();
+        if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            if let Ok(decoded) = serde_json::from_str::<Value>(inner) {
+                return std::borrow::Cow::Owned(decoded);
+            }
+        }
+    }
+    std::borrow::Cow::Borrowed(value)
+}
+
+fn field_label(config: &Config, key: &str) -> String {
+    match config.coloring {
+        crate::config::Coloring::Never => format!("{key}:"),
+        _ => format!("{}:", key.paint(config.theme.field_key)),
+    }
+}
+
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn field_value(config: &Config, value: &Value) -> String {
+    let text = field_text(value);
+    if config.coloring == crate::config::Coloring::Never {
+        return text;
+    }
+    let style = match value {
+        Value::String(_) => config.theme.field_string,
+        Value::Number(_) => config.theme.field_number,
+        Value::Bool(_) => config.theme.field_bool,
+        Value::Null => config.theme.field_null,
+        _ => config.theme.field_other,
+    };
+    text.paint(style).to_string()
+}
+
+fn dim(config: &Config, text: &str) -> String {
+    match config.coloring {
+        crate::config::Coloring::Never => text.to_string(),
+        _ => text.paint(config.theme.hint).to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use chrono::{DateTime, Utc};
+    use yansi::{Paint, Style};
 
     use crate::config::Config;
-    use crate::model::{ParsedLine, StructuredLog};
+    use crate::model::{AccessInfo, ParsedLine, RecordPart, StructuredLog, TextSpan};
     use crate::parser::ParseState;
+    use crate::theme::Theme;
 
     #[test]
     fn render_structured_log_appends_extra_fields() {
@@ -352,6 +850,8 @@ mod tests {
                 stacktrace: None,
                 raw_json: None,
                 kail_prefix: None,
+                source: None,
+                access: None,
             },
             None,
         );
@@ -378,6 +878,8 @@ mod tests {
                 stacktrace: None,
                 raw_json: None,
                 kail_prefix: None,
+                source: None,
+                access: None,
             },
             Some("+12ms"),
         );
@@ -385,17 +887,216 @@ mod tests {
         assert_eq!(rendered.delta, " +12ms   ");
     }
 
+    #[test]
+    fn highlight_access_colors_status_and_slow_latency() {
+        let config = Config {
+            latency_warning: Duration::from_millis(100),
+            latency_critical: Duration::from_secs(1),
+            ..Config::default()
+        };
+        let span = |part, start, end| Some(TextSpan { part, start, end });
+        let message = "GET /250ms-> 503 -> 503 (250ms)";
+        let access = AccessInfo {
+            status: 503,
+            duration: Some(Duration::from_millis(250)),
+            status_span: span(RecordPart::Message, 20, 23),
+            latency_span: span(RecordPart::Message, 25, 30),
+        };
+        let rendered = super::highlight_access(
+            &config,
+            Some(access),
+            RecordPart::Message,
+            message,
+            Style::new(),
+        );
+        assert_eq!(
+            rendered,
+            format!(
+                "GET /250ms-> 503 -> {} ({})",
+                "503".red().bold(),
+                "250ms".yellow()
+            )
+        );
+
+        let fast = AccessInfo {
+            status: 200,
+            duration: Some(Duration::from_millis(5)),
+            status_span: span(RecordPart::Message, 6, 9),
+            latency_span: span(RecordPart::Others, 0, 3),
+        };
+        let rendered = super::highlight_access(
+            &config,
+            Some(fast),
+            RecordPart::Others,
+            "5ms up ",
+            Style::new(),
+        );
+        assert_eq!(crate::utils::strip_ansi(&rendered), "5ms up ");
+        assert!(!rendered.contains("\x1b[33m"));
+    }
+
+    fn fields_log(
+        extra_fields: Vec<(String, String)>,
+        raw_json: Option<serde_json::Value>,
+    ) -> StructuredLog {
+        StructuredLog {
+            level: "INFO".to_string(),
+            message: "hello".to_string(),
+            timestamp: None,
+            parsed_timestamp: None,
+            others: None,
+            consumed_fields: Vec::new(),
+            extra_fields,
+            stacktrace: None,
+            raw_json,
+            kail_prefix: None,
+            source: None,
+            access: None,
+        }
+    }
+
+    #[test]
+    fn nested_field_highlights_style_their_field() {
+        let mut config = Config {
+            field_highlights: vec![
+                crate::highlight::FieldHighlight::parse("red:http.status>=500").unwrap(),
+            ],
+            ..Config::default()
+        };
+        let raw_json = serde_json::json!({"http": {"status": 503, "path": "/"}, "user": "bob"});
+        let log = fields_log(
+            vec![
+                ("http".to_string(), raw_json["http"].to_string()),
+                ("user".to_string(), "bob".to_string()),
+            ],
+            Some(raw_json),
+        );
+
+        assert_eq!(
+            super::render_extra_fields(&config, &log),
+            format!(
+                " {}={} {}=bob",
+                "http".paint(config.theme.field_key),
+                r#"{"path":"/","status":503}"#.red(),
+                "user".paint(config.theme.field_key),
+            )
+        );
+
+        config.expand_fields = true;
+        let lines = super::render_expanded_fields(&config, &log);
+        assert_eq!(
+            lines[2],
+            format!(
+                "      {}: {}",
+                "status".paint(config.theme.field_key),
+                "503".red()
+            )
+        );
+        assert!(!lines[1].contains("\x1b[31m"));
+    }
+
+    #[test]
+    fn expanded_fields_keep_the_json_types() {
+        let config = Config {
+            expand_fields: true,
+            ..Config::default()
+        };
+        let raw_json = serde_json::json!({"code": "42", "retry": "true", "count": 42});
+        let log = fields_log(
+            vec![
+                ("code".to_string(), "42".to_string()),
+                ("retry".to_string(), "true".to_string()),
+                ("count".to_string(), "42".to_string()),
+            ],
+            Some(raw_json),
+        );
+        let label = |key: &str| format!("{}:", key.paint(config.theme.field_key));
+
+        assert_eq!(
+            super::render_expanded_fields(&config, &log),
+            vec![
+                format!(
+                    "    {} {}",
+                    label("code"),
+                    "42".paint(config.theme.field_string)
+                ),
+                format!(
+                    "    {} {}",
+                    label("retry"),
+                    "true".paint(config.theme.field_string)
+                ),
+                format!(
+                    "    {} {}",
+                    label("count"),
+                    "42".paint(config.theme.field_number)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn expanded_fields_render_a_tree() {
+        let config = Config {
+            coloring: crate::config::Coloring::Never,
+            expand_fields: true,
+            expand_depth: 2,
+            expand_array_limit: 2,
+            ..Config::default()
+        };
+        let extra_fields = vec![
+            (
+                "service".to_string(),
+                r#"{"name":"api","ports":[80,443,8080],"meta":{"deep":{"er":1}}}"#.to_string(),
+            ),
+            (
+                "payload".to_string(),
+                r#"{"inner":"{\"ok\":true}"}"#.to_string(),
+            ),
+            ("user".to_string(), "bob".to_string()),
+        ];
+
+        assert_eq!(
+            super::render_expanded_fields(&config, &fields_log(extra_fields, None)),
+            vec![
+                "    service:",
+                "      meta:",
+                r#"        deep: {"er":1}"#,
+                "      name: api",
+                "      ports:",
+                "        - 80",
+                "        - 443",
+                "        … 1 more",
+                "    payload:",
+                "      inner:",
+                "        ok: true",
+                "    user: bob",
+            ]
+        );
+    }
+
     #[test]
     fn format_stack_line_respects_coloring_toggle() {
         let line = "/foo/bar.rs:42";
-        assert!(super::format_stack_line(line, false).contains("\x1b["));
-        assert_eq!(super::format_stack_line(line, true), "/foo/bar.rs:42");
+        assert!(super::format_stack_line(line, &Theme::default(), false).contains("\x1b["));
+        assert_eq!(
+            super::format_stack_line(line, &Theme::default(), true),
+            "/foo/bar.rs:42"
+        );
+        assert_eq!(
+            super::format_stack_line(line, &Theme::builtin("light").unwrap(), false),
+            format!(
+                "{}{}{}",
+                "/foo/".fixed(242),
+                "bar.rs".fixed(130).bold(),
+                ":42".fixed(28)
+            )
+        );
     }
 
     #[test]
     fn colorize_object_type_assigns_color_codes() {
-        let pod = super::colorize_object_type("pod/foo");
-        let deployment = super::colorize_object_type("deployment/bar");
+        let pod = super::colorize_object_type(&Theme::default(), "pod/foo");
+        let deployment = super::colorize_object_type(&Theme::default(), "deployment/bar");
         assert!(pod.contains("\x1b["));
         assert!(deployment.contains("\x1b["));
         assert_ne!(pod, deployment);
@@ -448,6 +1149,8 @@ mod tests {
             stacktrace: None,
             raw_json: None,
             kail_prefix: None,
+            source: None,
+            access: None,
         }
     }
 
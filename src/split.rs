@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{LineWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::app::OutputEvent;
+use crate::config::{Config, SplitFormat};
+use crate::model::ParsedLine;
+
+/// Writes every output event to a per-source file inside a directory, next
+/// to the combined view printed on stdout.
+pub struct SplitWriter {
+    directory: PathBuf,
+    format: SplitFormat,
+    input: String,
+    files: HashMap<String, LineWriter<File>>,
+}
+
+impl SplitWriter {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let directory = PathBuf::from(config.split_output.as_ref()?);
+        if let Err(error) = fs::create_dir_all(&directory) {
+            eprintln!(
+                "cannot create split output directory {}: {error}",
+                directory.display()
+            );
+            return None;
+        }
+
+        Some(Self {
+            directory,
+            format: config.split_format,
+            input: String::from("stdin"),
+            files: HashMap::new(),
+        })
+    }
+
+    /// Set the input name used for lines which do not carry a kail source.
+    pub fn set_input(&mut self, filename: &str) {
+        self.input = Path::new(filename).file_stem().map_or_else(
+            || filename.to_string(),
+            |stem| stem.to_string_lossy().to_string(),
+        );
+    }
+
+    pub fn write(&mut self, event: &OutputEvent) {
+        let lines = match self.format {
+            SplitFormat::Rendered => event
+                .rendered_lines
+                .iter()
+                .map(|line| crate::utils::strip_ansi(line))
+                .collect(),
+            SplitFormat::Ndjson => event
+                .parsed
+                .as_ref()
+                .and_then(ndjson_record)
+                .into_iter()
+                .collect::<Vec<_>>(),
+        };
+        if lines.is_empty() {
+            return;
+        }
+
+        let name = split_file_name(
+            event
+                .parsed
+                .as_ref()
+                .and_then(source_name)
+                .as_deref()
+                .unwrap_or(&self.input),
+            self.format,
+        );
+        let Some(file) = self.file(&name) else {
+            return;
+        };
+        for line in lines {
+            if let Err(error) = writeln!(file, "{line}") {
+                eprintln!("cannot write split output {name}: {error}");
+                return;
+            }
+        }
+    }
+
+    fn file(&mut self, name: &str) -> Option<&mut LineWriter<File>> {
+        if !self.files.contains_key(name) {
+            let path = self.directory.join(name);
+            match File::create(&path) {
+                Ok(file) => {
+                    self.files.insert(name.to_string(), LineWriter::new(file));
+                }
+                Err(error) => {
+                    eprintln!("cannot create split output {}: {error}", path.display());
+                    return None;
+                }
+            }
+        }
+        self.files.get_mut(name)
+    }
+}
+
+fn source_name(parsed: &ParsedLine) -> Option<String> {
+    match parsed {
+        ParsedLine::Structured(log) => log.source.as_ref().map(ToString::to_string),
+        ParsedLine::Raw(line, _) => crate::parser::parse_kail_source(line).map(|s| s.to_string()),
+        ParsedLine::KubectlHeader | ParsedLine::KubectlEvent(_) => None,
+    }
+}
+
+fn split_file_name(source: &str, format: SplitFormat) -> String {
+    let stem: String = source
+        .trim_end_matches(']')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let extension = match format {
+        SplitFormat::Rendered => "log",
+        SplitFormat::Ndjson => "ndjson",
+    };
+    format!("{stem}.{extension}")
+}
+
+fn ndjson_record(parsed: &ParsedLine) -> Option<String> {
+    match parsed {
+        ParsedLine::Structured(log) => Some(log.raw_json.as_ref().map_or_else(
+            || json!({"level": log.level, "message": log.message}).to_string(),
+            ToString::to_string,
+        )),
+        ParsedLine::Raw(line, _) => {
+            let message = crate::parser::strip_kail_prefix(line);
+            Some(json!({ "message": message }).to_string())
+        }
+        ParsedLine::KubectlHeader => None,
+        ParsedLine::KubectlEvent(event) => Some(
+            json!({
+                "last_seen": event.last_seen,
+                "type": event.type_,
+                "reason": event.reason,
+                "object": event.object,
+                "message": event.message,
+            })
+            .to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::OutputEvent;
+    use crate::config::{Config, SplitFormat};
+    use crate::parser::ParseState;
+
+    #[test]
+    fn split_file_name_sanitizes_kail_sources() {
+        assert_eq!(
+            super::split_file_name("ns/pod-1[manager]", SplitFormat::Rendered),
+            "ns_pod-1_manager.log"
+        );
+        assert_eq!(
+            super::split_file_name("stdin", SplitFormat::Ndjson),
+            "stdin.ndjson"
+        );
+    }
+
+    #[test]
+    fn split_writer_writes_one_file_per_source() {
+        let directory = tempfile::tempdir().unwrap();
+        let config = Config {
+            split_output: Some(directory.path().to_str().unwrap().to_string()),
+            split_format: SplitFormat::Ndjson,
+            ..Config::default()
+        };
+        let mut writer = super::SplitWriter::from_config(&config).unwrap();
+        writer.set_input("/var/log/app.log");
+
+        for line in [
+            r#"ns/api[web]: {"level":"info","msg":"one"}"#,
+            r#"ns/db[pg]: {"level":"info","msg":"two"}"#,
+            "plain line",
+        ] {
+            let mut state = ParseState::default();
+            for event in crate::app::process_raw_line(&config, line, &mut state) {
+                writer.write(&event);
+            }
+        }
+        writer.write(&OutputEvent {
+            collapse_key: None,
+            rendered_lines: vec!["summary".to_string()],
+            parsed: None,
+        });
+        drop(writer);
+
+        let read = |name: &str| std::fs::read_to_string(directory.path().join(name)).unwrap();
+        assert_eq!(
+            read("ns_api_web.ndjson"),
+            "{\"level\":\"info\",\"msg\":\"one\"}\n"
+        );
+        assert_eq!(
+            read("ns_db_pg.ndjson"),
+            "{\"level\":\"info\",\"msg\":\"two\"}\n"
+        );
+        assert_eq!(read("app.ndjson"), "{\"message\":\"plain line\"}\n");
+    }
+}
@@ -0,0 +1,344 @@
+use yansi::{Color, Paint, Style};
+
+/// A `--template` line layout, placeholders are written as
+/// `{name[:format][|style]}` and `{field:path[:format][|style]}`, the format
+/// being an optional `<`/`>` alignment, a width and a `.max` truncation.
+/// Segments between `{?` and `?}` are only shown when one of their
+/// placeholders is not empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+    Conditional(Vec<Segment>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Placeholder {
+    name: Name,
+    right_align: bool,
+    width: Option<usize>,
+    max: Option<usize>,
+    style: Option<Style>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Name {
+    Time,
+    Level,
+    Delta,
+    Prefix,
+    Others,
+    Message,
+    Extras,
+    Field(String),
+}
+
+/// The value of a placeholder, `styled` is used as is unless the value gets
+/// truncated or has an explicit style, then `plain` is painted with `style`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateValue {
+    pub plain: String,
+    pub styled: String,
+    pub style: Style,
+}
+
+impl TemplateValue {
+    pub fn plain(value: String) -> Self {
+        Self {
+            styled: value.clone(),
+            plain: value,
+            style: Style::new(),
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let (segments, rest) = parse_segments(template, false)?;
+        if !rest.is_empty() {
+            return Err(format!("unexpected '?}}' in template '{template}'"));
+        }
+        Ok(Self { segments })
+    }
+
+    pub fn render(
+        &self,
+        coloring: bool,
+        lookup: &dyn Fn(&Name) -> Option<TemplateValue>,
+    ) -> String {
+        render_segments(&self.segments, coloring, lookup).0
+    }
+}
+
+fn parse_segments(input: &str, nested: bool) -> Result<(Vec<Segment>, &str), String> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = input;
+
+    loop {
+        if nested {
+            if let Some(after) = rest.strip_prefix("?}") {
+                push_literal(&mut segments, &mut literal);
+                return Ok((segments, after));
+            }
+        } else if rest.starts_with("?}") {
+            push_literal(&mut segments, &mut literal);
+            return Ok((segments, rest));
+        }
+
+        let Some(c) = rest.chars().next() else {
+            if nested {
+                return Err("unterminated '{?' conditional segment".to_string());
+            }
+            push_literal(&mut segments, &mut literal);
+            return Ok((segments, rest));
+        };
+
+        if let Some(after) = rest.strip_prefix("{{") {
+            literal.push('{');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("}}") {
+            literal.push('}');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("{?") {
+            push_literal(&mut segments, &mut literal);
+            let (inner, after) = parse_segments(after, true)?;
+            segments.push(Segment::Conditional(inner));
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("unterminated placeholder '{{{after}'"))?;
+            push_literal(&mut segments, &mut literal);
+            segments.push(Segment::Placeholder(parse_placeholder(&after[..end])?));
+            rest = &after[end + 1..];
+        } else {
+            literal.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+}
+
+fn push_literal(segments: &mut Vec<Segment>, literal: &mut String) {
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(std::mem::take(literal)));
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Placeholder, String> {
+    let (spec, style) = match placeholder.split_once('|') {
+        Some((spec, style)) => (spec, Some(parse_style(style)?)),
+        None => (placeholder, None),
+    };
+    let mut parts = spec.split(':');
+    let name = match parts.next().unwrap_or_default().trim() {
+        "time" | "ts" | "timestamp" => Name::Time,
+        "level" => Name::Level,
+        "delta" => Name::Delta,
+        "prefix" => Name::Prefix,
+        "others" => Name::Others,
+        "msg" | "message" => Name::Message,
+        "extras" => Name::Extras,
+        "field" => Name::Field(
+            parts
+                .next()
+                .filter(|path| !path.is_empty())
+                .ok_or_else(|| format!("missing field path in '{{{placeholder}}}'"))?
+                .to_string(),
+        ),
+        name => return Err(format!("unknown template placeholder '{name}'")),
+    };
+
+    let mut placeholder = Placeholder {
+        name,
+        right_align: false,
+        width: None,
+        max: None,
+        style,
+    };
+    if let Some(format) = parts.next() {
+        let format = if let Some(format) = format.strip_prefix('>') {
+            placeholder.right_align = true;
+            format
+        } else {
+            format.strip_prefix('<').unwrap_or(format)
+        };
+        let (width, max) = format.split_once('.').unwrap_or((format, ""));
+        let number = |value: &str| {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("invalid template format '{format}'"))
+        };
+        if !width.is_empty() {
+            placeholder.width = Some(number(width)?);
+        }
+        if !max.is_empty() {
+            placeholder.max = Some(number(max)?);
+        }
+    }
+    Ok(placeholder)
+}
+
+/// Parse a comma separated style like `red,bold`, `bg=blue,208` or `#268bd2`.
+pub fn parse_style(style: &str) -> Result<Style, String> {
+    let mut parsed = Style::new();
+    for token in style.split(',').map(str::trim) {
+        parsed = match token {
+            "bold" => parsed.bold(),
+            "dim" => parsed.dim(),
+            "italic" => parsed.italic(),
+            "underline" => parsed.underline(),
+            "plain" => parsed,
+            token => {
+                let (background, color) = token
+                    .strip_prefix("bg=")
+                    .map_or((false, token), |color| (true, color));
+                let rgb = color
+                    .strip_prefix('#')
+                    .filter(|hex| hex.len() == 6 && hex.is_ascii())
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+                let color = if let Ok(fixed) = color.parse::<u8>() {
+                    Color::Fixed(fixed)
+                } else if let Some(rgb) = rgb {
+                    let [_, red, green, blue] = rgb.to_be_bytes();
+                    Color::Rgb(red, green, blue)
+                } else {
+                    match crate::cli::match_color(color, Color::Primary) {
+                        Color::Primary => return Err(format!("unknown style '{token}'")),
+                        color => color,
+                    }
+                };
+                if background {
+                    parsed.bg(color)
+                } else {
+                    parsed.fg(color)
+                }
+            }
+        };
+    }
+    Ok(parsed)
+}
+
+/// Returns the rendered segments and whether a placeholder had a value.
+fn render_segments(
+    segments: &[Segment],
+    coloring: bool,
+    lookup: &dyn Fn(&Name) -> Option<TemplateValue>,
+) -> (String, bool) {
+    let mut rendered = String::new();
+    let mut has_value = false;
+    for segment in segments {
+        match segment {
+            Segment::Literal(literal) => rendered.push_str(literal),
+            Segment::Placeholder(placeholder) => {
+                let value = lookup(&placeholder.name).filter(|value| !value.plain.is_empty());
+                has_value |= value.is_some();
+                rendered.push_str(&render_placeholder(placeholder, value, coloring));
+            }
+            Segment::Conditional(inner) => {
+                let (inner, inner_has_value) = render_segments(inner, coloring, lookup);
+                if inner_has_value {
+                    rendered.push_str(&inner);
+                    has_value = true;
+                }
+            }
+        }
+    }
+    (rendered, has_value)
+}
+
+fn render_placeholder(
+    placeholder: &Placeholder,
+    value: Option<TemplateValue>,
+    coloring: bool,
+) -> String {
+    let value = value.unwrap_or_else(|| TemplateValue::plain(String::new()));
+    let truncated = placeholder
+        .max
+        .filter(|max| value.plain.chars().count() > *max)
+        .map(|max| {
+            let mut truncated: String = value.plain.chars().take(max.saturating_sub(1)).collect();
+            truncated.push('…');
+            truncated
+        });
+    let plain = truncated.as_deref().unwrap_or(&value.plain);
+    let text = if !coloring {
+        plain.to_string()
+    } else if let Some(style) = placeholder.style {
+        plain.paint(style).to_string()
+    } else if truncated.is_some() {
+        plain.paint(value.style).to_string()
+    } else {
+        value.styled.clone()
+    };
+
+    let padding = placeholder
+        .width
+        .map_or(0, |width| width.saturating_sub(plain.chars().count()));
+    if placeholder.right_align {
+        format!("{}{text}", " ".repeat(padding))
+    } else {
+        format!("{text}{}", " ".repeat(padding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yansi::{Paint, Style};
+
+    use super::{Name, Template, TemplateValue};
+
+    fn lookup(name: &Name) -> Option<TemplateValue> {
+        match name {
+            Name::Time => Some(TemplateValue::plain("10:00:00".to_string())),
+            Name::Level => Some(TemplateValue {
+                plain: "INFO".to_string(),
+                styled: "INFO".green().to_string(),
+                style: Style::new().green(),
+            }),
+            Name::Message => Some(TemplateValue::plain("a long message".to_string())),
+            Name::Field(path) if path == "component" => {
+                Some(TemplateValue::plain("api".to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn renders_placeholders_with_formats() {
+        let template =
+            Template::parse("{time} {level:5}|{level:>6} [{field:component}] {msg:.6}").unwrap();
+        assert_eq!(
+            template.render(false, &lookup),
+            "10:00:00 INFO |  INFO [api] a lon…"
+        );
+        assert_eq!(
+            Template::parse("{level} {msg:.6|red}")
+                .unwrap()
+                .render(true, &lookup),
+            format!("{} {}", "INFO".green(), "a lon…".red())
+        );
+    }
+
+    #[test]
+    fn conditional_segments_and_escapes() {
+        let template =
+            Template::parse("{{{msg}}}{? ({field:missing})?}{? <{field:component}>?}").unwrap();
+        assert_eq!(template.render(false, &lookup), "{a long message} <api>");
+    }
+
+    #[test]
+    fn reports_template_errors() {
+        assert!(Template::parse("{nope}").is_err());
+        assert!(Template::parse("{field}").is_err());
+        assert!(Template::parse("{msg:abc}").is_err());
+        assert!(Template::parse("{msg|sparkly}").is_err());
+        assert!(Template::parse("{? {msg}").is_err());
+        assert!(Template::parse("{msg").is_err());
+    }
+}
@@ -0,0 +1,610 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use yansi::Style;
+
+pub const THEMES: [&str; 5] = ["dark", "light", "solarized", "high-contrast", "mono"];
+
+/// The styles of everything snazy paints by key, the other themes are applied
+/// on top of this one. The styles use the `--template` syntax.
+const DARK: &[(&str, &str)] = &[
+    ("level.trace", "8"),
+    ("level.debug", "14"),
+    ("level.info", "green"),
+    ("level.notice", "cyan"),
+    ("level.warning", "yellow"),
+    ("level.error", "red"),
+    ("level.critical", "red,bold"),
+    ("level.fatal", "red"),
+    ("level.other", "4"),
+    ("timestamp", "13"),
+    ("delta", "8"),
+    ("others", "cyan,italic"),
+    ("prefix", "blue"),
+    ("header", "bold"),
+    ("rule", "8"),
+    ("hint", "8,italic"),
+    ("mark", "magenta,bold"),
+    ("field.key", "bold"),
+    ("field.string", "green"),
+    ("field.number", "yellow"),
+    ("field.bool", "magenta"),
+    ("field.null", "8"),
+    ("field.other", "cyan"),
+    ("stack.header", "red,bold"),
+    ("stack.path", "15"),
+    ("stack.file", "yellow,bold"),
+    ("stack.line", "green"),
+    ("stack.function", "cyan,bold"),
+    ("status.2xx", "green"),
+    ("status.3xx", "cyan"),
+    ("status.4xx", "yellow"),
+    ("status.5xx", "red,bold"),
+    ("latency.warning", "yellow"),
+    ("latency.critical", "red,bold"),
+    ("kubectl.warning", "red,bold"),
+    ("kubectl.normal", "green,bold"),
+    ("kubectl.other", "bold"),
+    ("kubectl.reason", "yellow,bold"),
+    ("kubectl.last-seen", "bold"),
+    ("object.pod", "magenta,bold"),
+    ("object.replicaset", "blue,bold"),
+    ("object.deployment", "green,bold"),
+    ("object.service", "yellow,bold"),
+    ("object.job", "cyan,bold"),
+    ("object.daemonset", "red,bold"),
+    ("object.statefulset", "93,bold"),
+    ("object.configmap", "208,bold"),
+    ("object.secret", "244,bold"),
+    ("object.other", "white,bold"),
+    ("highlights", "cyan yellow red magenta blue"),
+    ("preset.url", "blue,underline"),
+    ("preset.uuid", "141"),
+    ("preset.ip", "magenta"),
+    ("preset.sha", "yellow"),
+    ("preset.k8s", "magenta,bold"),
+    ("preset.path", "178"),
+    ("preset.http", "green,bold"),
+    ("preset.duration", "cyan"),
+    ("preset.quoted", "green"),
+];
+
+const LIGHT: &[(&str, &str)] = &[
+    ("level.trace", "245"),
+    ("level.debug", "31"),
+    ("level.info", "28"),
+    ("level.notice", "30"),
+    ("level.warning", "130"),
+    ("level.error", "160"),
+    ("level.critical", "160,bold"),
+    ("level.fatal", "160,bold"),
+    ("level.other", "242"),
+    ("timestamp", "90"),
+    ("delta", "245"),
+    ("others", "30,italic"),
+    ("prefix", "25"),
+    ("rule", "250"),
+    ("hint", "245,italic"),
+    ("mark", "127,bold"),
+    ("field.string", "28"),
+    ("field.number", "130"),
+    ("field.bool", "127"),
+    ("field.null", "245"),
+    ("field.other", "30"),
+    ("stack.header", "160,bold"),
+    ("stack.path", "242"),
+    ("stack.file", "130,bold"),
+    ("stack.line", "28"),
+    ("stack.function", "25,bold"),
+    ("status.2xx", "28"),
+    ("status.3xx", "30"),
+    ("status.4xx", "130"),
+    ("status.5xx", "160,bold"),
+    ("latency.warning", "130"),
+    ("latency.critical", "160,bold"),
+    ("kubectl.warning", "160,bold"),
+    ("kubectl.normal", "28,bold"),
+    ("kubectl.reason", "130,bold"),
+    ("object.pod", "127,bold"),
+    ("object.replicaset", "25,bold"),
+    ("object.deployment", "28,bold"),
+    ("object.service", "130,bold"),
+    ("object.job", "30,bold"),
+    ("object.daemonset", "160,bold"),
+    ("object.configmap", "166,bold"),
+    ("object.secret", "242,bold"),
+    ("object.other", "black,bold"),
+    ("highlights", "30 130 160 127 25"),
+    ("preset.url", "25,underline"),
+    ("preset.uuid", "91"),
+    ("preset.ip", "127"),
+    ("preset.sha", "130"),
+    ("preset.k8s", "127,bold"),
+    ("preset.path", "94"),
+    ("preset.http", "28,bold"),
+    ("preset.duration", "30"),
+    ("preset.quoted", "28"),
+];
+
+const SOLARIZED: &[(&str, &str)] = &[
+    ("level.trace", "#586e75"),
+    ("level.debug", "#2aa198"),
+    ("level.info", "#859900"),
+    ("level.notice", "#268bd2"),
+    ("level.warning", "#b58900"),
+    ("level.error", "#dc322f"),
+    ("level.critical", "#dc322f,bold"),
+    ("level.fatal", "#dc322f,bold"),
+    ("level.other", "#6c71c4"),
+    ("timestamp", "#d33682"),
+    ("delta", "#586e75"),
+    ("others", "#2aa198,italic"),
+    ("prefix", "#268bd2"),
+    ("rule", "#586e75"),
+    ("hint", "#586e75,italic"),
+    ("mark", "#d33682,bold"),
+    ("field.string", "#859900"),
+    ("field.number", "#b58900"),
+    ("field.bool", "#d33682"),
+    ("field.null", "#586e75"),
+    ("field.other", "#2aa198"),
+    ("stack.header", "#dc322f,bold"),
+    ("stack.path", "#93a1a1"),
+    ("stack.file", "#b58900,bold"),
+    ("stack.line", "#859900"),
+    ("stack.function", "#2aa198,bold"),
+    ("status.2xx", "#859900"),
+    ("status.3xx", "#2aa198"),
+    ("status.4xx", "#b58900"),
+    ("status.5xx", "#dc322f,bold"),
+    ("latency.warning", "#cb4b16"),
+    ("latency.critical", "#dc322f,bold"),
+    ("kubectl.warning", "#dc322f,bold"),
+    ("kubectl.normal", "#859900,bold"),
+    ("kubectl.reason", "#b58900,bold"),
+    ("object.pod", "#d33682,bold"),
+    ("object.replicaset", "#268bd2,bold"),
+    ("object.deployment", "#859900,bold"),
+    ("object.service", "#b58900,bold"),
+    ("object.job", "#2aa198,bold"),
+    ("object.daemonset", "#dc322f,bold"),
+    ("object.statefulset", "#6c71c4,bold"),
+    ("object.configmap", "#cb4b16,bold"),
+    ("object.secret", "#586e75,bold"),
+    ("object.other", "#93a1a1,bold"),
+    ("highlights", "#2aa198 #b58900 #dc322f #d33682 #268bd2"),
+    ("preset.url", "#268bd2,underline"),
+    ("preset.uuid", "#6c71c4"),
+    ("preset.ip", "#d33682"),
+    ("preset.sha", "#b58900"),
+    ("preset.k8s", "#d33682,bold"),
+    ("preset.path", "#cb4b16"),
+    ("preset.http", "#859900,bold"),
+    ("preset.duration", "#2aa198"),
+    ("preset.quoted", "#859900"),
+];
+
+const HIGH_CONTRAST: &[(&str, &str)] = &[
+    ("level.trace", "15"),
+    ("level.debug", "14,bold"),
+    ("level.info", "10,bold"),
+    ("level.notice", "12,bold"),
+    ("level.warning", "black,bg=11,bold"),
+    ("level.error", "white,bg=9,bold"),
+    ("level.critical", "white,bg=9,bold"),
+    ("level.fatal", "white,bg=9,bold"),
+    ("level.other", "15,bold"),
+    ("timestamp", "15"),
+    ("delta", "15"),
+    ("others", "14"),
+    ("prefix", "12,bold"),
+    ("rule", "15"),
+    ("hint", "15,italic"),
+    ("mark", "13,bold"),
+    ("field.key", "15,bold"),
+    ("field.string", "10"),
+    ("field.number", "11"),
+    ("field.bool", "13"),
+    ("field.null", "15"),
+    ("field.other", "14"),
+    ("stack.header", "white,bg=9,bold"),
+    ("stack.path", "15"),
+    ("stack.file", "11,bold"),
+    ("stack.line", "10,bold"),
+    ("stack.function", "14,bold"),
+    ("status.2xx", "10,bold"),
+    ("status.3xx", "14,bold"),
+    ("status.4xx", "black,bg=11,bold"),
+    ("status.5xx", "white,bg=9,bold"),
+    ("latency.warning", "black,bg=11,bold"),
+    ("latency.critical", "white,bg=9,bold"),
+    ("kubectl.warning", "white,bg=9,bold"),
+    ("kubectl.normal", "10,bold"),
+    ("kubectl.reason", "11,bold"),
+    (
+        "highlights",
+        "black,bg=14 black,bg=11 white,bg=9 black,bg=13 white,bg=12",
+    ),
+    ("preset.url", "12,bold,underline"),
+    ("preset.uuid", "13,bold"),
+    ("preset.ip", "13,bold"),
+    ("preset.sha", "11,bold"),
+    ("preset.k8s", "13,bold"),
+    ("preset.path", "11,underline"),
+    ("preset.http", "10,bold"),
+    ("preset.duration", "14,bold"),
+    ("preset.quoted", "10"),
+];
+
+const MONO: &[(&str, &str)] = &[
+    ("level.trace", "plain"),
+    ("level.debug", "plain"),
+    ("level.info", "bold"),
+    ("level.notice", "bold"),
+    ("level.warning", "bold"),
+    ("level.error", "bold,underline"),
+    ("level.critical", "bold,underline"),
+    ("level.fatal", "bold,underline"),
+    ("level.other", "plain"),
+    ("timestamp", "plain"),
+    ("delta", "plain"),
+    ("others", "italic"),
+    ("prefix", "plain"),
+    ("rule", "plain"),
+    ("hint", "italic"),
+    ("mark", "bold"),
+    ("field.string", "plain"),
+    ("field.number", "plain"),
+    ("field.bool", "plain"),
+    ("field.null", "plain"),
+    ("field.other", "plain"),
+    ("stack.header", "bold"),
+    ("stack.path", "plain"),
+    ("stack.file", "bold"),
+    ("stack.line", "plain"),
+    ("stack.function", "bold"),
+    ("status.2xx", "plain"),
+    ("status.3xx", "plain"),
+    ("status.4xx", "bold"),
+    ("status.5xx", "bold,underline"),
+    ("latency.warning", "bold"),
+    ("latency.critical", "bold,underline"),
+    ("kubectl.warning", "bold,underline"),
+    ("kubectl.normal", "bold"),
+    ("kubectl.reason", "bold"),
+    ("object.pod", "bold"),
+    ("object.replicaset", "bold"),
+    ("object.deployment", "bold"),
+    ("object.service", "bold"),
+    ("object.job", "bold"),
+    ("object.daemonset", "bold"),
+    ("object.statefulset", "bold"),
+    ("object.configmap", "bold"),
+    ("object.secret", "bold"),
+    ("object.other", "bold"),
+    (
+        "highlights",
+        "bold underline italic bold,underline bold,italic",
+    ),
+    ("preset.url", "underline"),
+    ("preset.uuid", "italic"),
+    ("preset.ip", "italic"),
+    ("preset.sha", "italic"),
+    ("preset.k8s", "bold"),
+    ("preset.path", "underline"),
+    ("preset.http", "bold"),
+    ("preset.duration", "italic"),
+    ("preset.quoted", "italic"),
+];
+
+/// A theme of the config file, it starts from the `inherits` theme (dark by
+/// default) and overrides some of its styles.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThemeDefinition {
+    pub inherits: Option<String>,
+    #[serde(flatten)]
+    pub styles: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    levels: HashMap<String, Style>,
+    level_other: Style,
+    objects: HashMap<String, Style>,
+    object_other: Style,
+    presets: HashMap<String, Style>,
+    pub timestamp: Style,
+    pub delta: Style,
+    pub others: Style,
+    pub prefix: Style,
+    pub header: Style,
+    pub rule: Style,
+    pub hint: Style,
+    pub mark: Style,
+    pub field_key: Style,
+    pub field_string: Style,
+    pub field_number: Style,
+    pub field_bool: Style,
+    pub field_null: Style,
+    pub field_other: Style,
+    pub stack_header: Style,
+    pub stack_path: Style,
+    pub stack_file: Style,
+    pub stack_line: Style,
+    pub stack_function: Style,
+    pub status_2xx: Style,
+    pub status_3xx: Style,
+    pub status_4xx: Style,
+    pub status_5xx: Style,
+    pub latency_warning: Style,
+    pub latency_critical: Style,
+    pub kubectl_warning: Style,
+    pub kubectl_normal: Style,
+    pub kubectl_other: Style,
+    pub kubectl_reason: Style,
+    pub kubectl_last_seen: Style,
+    pub highlights: Vec<Style>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::builtin("dark").unwrap()
+    }
+}
+
+impl Theme {
+    fn unstyled() -> Self {
+        let plain = Style::new();
+        Self {
+            levels: HashMap::new(),
+            level_other: plain,
+            objects: HashMap::new(),
+            object_other: plain,
+            presets: HashMap::new(),
+            timestamp: plain,
+            delta: plain,
+            others: plain,
+            prefix: plain,
+            header: plain,
+            rule: plain,
+            hint: plain,
+            mark: plain,
+            field_key: plain,
+            field_string: plain,
+            field_number: plain,
+            field_bool: plain,
+            field_null: plain,
+            field_other: plain,
+            stack_header: plain,
+            stack_path: plain,
+            stack_file: plain,
+            stack_line: plain,
+            stack_function: plain,
+            status_2xx: plain,
+            status_3xx: plain,
+            status_4xx: plain,
+            status_5xx: plain,
+            latency_warning: plain,
+            latency_critical: plain,
+            kubectl_warning: plain,
+            kubectl_normal: plain,
+            kubectl_other: plain,
+            kubectl_reason: plain,
+            kubectl_last_seen: plain,
+            highlights: vec![plain],
+        }
+    }
+
+    /// A builtin theme, the other themes than dark only override its styles.
+    pub fn builtin(name: &str) -> Option<Self> {
+        let overrides = match name {
+            "dark" => &[][..],
+            "light" => LIGHT,
+            "solarized" => SOLARIZED,
+            "high-contrast" => HIGH_CONTRAST,
+            "mono" => MONO,
+            _ => return None,
+        };
+        let mut theme = Self::unstyled();
+        for (key, style) in DARK.iter().chain(overrides) {
+            theme
+                .set(key, style)
+                .unwrap_or_else(|error| panic!("builtin theme {name}: {error}"));
+        }
+        Some(theme)
+    }
+
+    /// Look up a theme by name, the config file themes come first so they
+    /// can replace a builtin one.
+    pub fn load(
+        name: &str,
+        definitions: &HashMap<String, ThemeDefinition>,
+    ) -> Result<Self, String> {
+        Self::load_inherited(name, definitions, &mut Vec::new())
+    }
+
+    fn load_inherited(
+        name: &str,
+        definitions: &HashMap<String, ThemeDefinition>,
+        seen: &mut Vec<String>,
+    ) -> Result<Self, String> {
+        let Some(definition) = definitions.get(name) else {
+            return Self::builtin(name).ok_or_else(|| {
+                let mut names: Vec<&str> = THEMES.to_vec();
+                names.extend(definitions.keys().map(String::as_str));
+                format!("unknown theme '{name}', available: {}", names.join(", "))
+            });
+        };
+        if seen.iter().any(|seen| seen == name) {
+            return Err(format!("theme '{name}' inherits from itself"));
+        }
+        seen.push(name.to_string());
+
+        // a config theme replacing a builtin one inherits from that builtin
+        let parent = definition.inherits.as_deref().unwrap_or("dark");
+        let replaces_builtin = Self::builtin(parent).is_some()
+            && (parent == name || seen.iter().any(|seen| seen == parent));
+        let mut theme = if definitions.contains_key(parent) && !replaces_builtin {
+            Self::load_inherited(parent, definitions, seen)?
+        } else {
+            Self::builtin(parent)
+                .ok_or_else(|| format!("theme '{name}' inherits from unknown theme '{parent}'"))?
+        };
+        let mut styles: Vec<_> = definition.styles.iter().collect();
+        styles.sort();
+        for (key, style) in styles {
+            theme
+                .set(key, style)
+                .map_err(|error| format!("theme '{name}': {error}"))?;
+        }
+        Ok(theme)
+    }
+
+    fn set(&mut self, key: &str, style: &str) -> Result<(), String> {
+        if key == "highlights" {
+            self.highlights = style
+                .split_whitespace()
+                .map(crate::template::parse_style)
+                .collect::<Result<_, _>>()?;
+            if self.highlights.is_empty() {
+                self.highlights.push(Style::new());
+            }
+            return Ok(());
+        }
+
+        let parsed = crate::template::parse_style(style)?;
+        if let Some(level) = key.strip_prefix("level.").filter(|level| *level != "other") {
+            self.levels.insert(level.to_uppercase(), parsed);
+            return Ok(());
+        }
+        if let Some(kind) = key.strip_prefix("object.").filter(|kind| *kind != "other") {
+            self.objects.insert(kind.to_lowercase(), parsed);
+            return Ok(());
+        }
+        if let Some(preset) = key.strip_prefix("preset.") {
+            <crate::config::HighlightPreset as clap::ValueEnum>::from_str(preset, false)
+                .map_err(|_| format!("unknown theme style '{key}'"))?;
+            self.presets.insert(preset.to_string(), parsed);
+            return Ok(());
+        }
+        let slot = match key {
+            "level.other" => &mut self.level_other,
+            "object.other" => &mut self.object_other,
+            "timestamp" => &mut self.timestamp,
+            "delta" => &mut self.delta,
+            "others" => &mut self.others,
+            "prefix" => &mut self.prefix,
+            "header" => &mut self.header,
+            "rule" => &mut self.rule,
+            "hint" => &mut self.hint,
+            "mark" => &mut self.mark,
+            "field.key" => &mut self.field_key,
+            "field.string" => &mut self.field_string,
+            "field.number" => &mut self.field_number,
+            "field.bool" => &mut self.field_bool,
+            "field.null" => &mut self.field_null,
+            "field.other" => &mut self.field_other,
+            "stack.header" => &mut self.stack_header,
+            "stack.path" => &mut self.stack_path,
+            "stack.file" => &mut self.stack_file,
+            "stack.line" => &mut self.stack_line,
+            "stack.function" => &mut self.stack_function,
+            "status.2xx" => &mut self.status_2xx,
+            "status.3xx" => &mut self.status_3xx,
+            "status.4xx" => &mut self.status_4xx,
+            "status.5xx" => &mut self.status_5xx,
+            "latency.warning" => &mut self.latency_warning,
+            "latency.critical" => &mut self.latency_critical,
+            "kubectl.warning" => &mut self.kubectl_warning,
+            "kubectl.normal" => &mut self.kubectl_normal,
+            "kubectl.other" => &mut self.kubectl_other,
+            "kubectl.reason" => &mut self.kubectl_reason,
+            "kubectl.last-seen" => &mut self.kubectl_last_seen,
+            key => return Err(format!("unknown theme style '{key}'")),
+        };
+        *slot = parsed;
+        Ok(())
+    }
+
+    /// The style of a normalized level like `WARNING`.
+    pub fn level(&self, level: &str) -> Style {
+        self.levels.get(level).copied().unwrap_or(self.level_other)
+    }
+
+    /// The style of a `--highlight-preset` like `url`.
+    pub fn preset(&self, preset: &str) -> Style {
+        self.presets.get(preset).copied().unwrap_or_default()
+    }
+
+    /// The style of a kubernetes object kind like `pod`.
+    pub fn object(&self, kind: &str) -> Style {
+        self.objects.get(kind).copied().unwrap_or(self.object_other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use yansi::Style;
+
+    use super::{Theme, ThemeDefinition, THEMES};
+
+    #[test]
+    fn builtin_themes_are_valid() {
+        for name in THEMES {
+            assert!(Theme::builtin(name).is_some(), "{name}");
+        }
+        let dark = Theme::default();
+        assert_eq!(dark.level("DEBUG"), Style::new().fixed(14));
+        assert_eq!(dark.level("AUDIT"), Style::new().fixed(4));
+        assert_eq!(dark.object("pod"), Style::new().magenta().bold());
+        assert_eq!(
+            Theme::builtin("mono").unwrap().level("INFO"),
+            Style::new().bold()
+        );
+    }
+
+    #[test]
+    fn config_file_themes_inherit_and_override() {
+        let definitions: HashMap<String, ThemeDefinition> = serde_json::from_str(
+            r#"{
+                "paper": {"inherits": "light", "level.debug": "blue,italic", "object.route": "green"},
+                "loop": {"inherits": "loop"},
+                "broken": {"timestamp": "sparkly"}
+            }"#,
+        )
+        .unwrap();
+
+        let paper = Theme::load("paper", &definitions).unwrap();
+        assert_eq!(paper.level("DEBUG"), Style::new().blue().italic());
+        assert_eq!(paper.level("INFO"), Style::new().fixed(28));
+        assert_eq!(paper.object("route"), Style::new().green());
+
+        assert!(Theme::load("loop", &definitions).is_err());
+        assert!(Theme::load("broken", &definitions).is_err());
+        assert!(Theme::load("nope", &definitions)
+            .unwrap_err()
+            .contains("available: dark, light"));
+    }
+
+    #[test]
+    fn config_file_themes_replace_builtin_ones() {
+        let definitions: HashMap<String, ThemeDefinition> = serde_json::from_str(
+            r#"{
+                "dark": {"level.info": "blue"},
+                "light": {"inherits": "paper"},
+                "paper": {"inherits": "light", "level.debug": "green"}
+            }"#,
+        )
+        .unwrap();
+
+        let dark = Theme::load("dark", &definitions).unwrap();
+        assert_eq!(dark.level("INFO"), Style::new().blue());
+        assert_eq!(dark.level("DEBUG"), Style::new().fixed(14));
+
+        let light = Theme::load("light", &definitions).unwrap();
+        assert_eq!(light.level("DEBUG"), Style::new().green());
+        assert_eq!(light.level("INFO"), Style::new().fixed(28));
+    }
+}
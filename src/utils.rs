@@ -1,32 +1,53 @@
-use chrono::{DateTime, LocalResult, TimeDelta, TimeZone, Utc};
+use chrono::{DateTime, Datelike, LocalResult, NaiveDateTime, TimeDelta, TimeZone, Utc};
 use chrono_tz::Tz;
 use regex::Regex;
 use serde_json::{Number, Value};
 use std::collections::HashMap;
+use std::sync::LazyLock;
 use std::time::Duration;
 use yansi::Paint;
-use yansi::Style;
-
-/// replace info level DEBUG, WARNING, ERROR, INFO, FATAL by pretty characters
-pub fn level_symbols(level: &str) -> String {
-    match level {
-        "DEBUG" => "🐛".to_string(),
-        "WARNING" => "⚠️".to_string(),
-        "ERROR" => "🚨".to_string(),
-        "INFO" => "💡".to_string(),
-        "FATAL" => "💀".to_string(),
-        _ => "∙".to_string(),
+
+/// The visible width of a level label without an explicit width.
+const LEVEL_WIDTH: usize = 19;
+
+/// The visible width of a colored level label without an explicit width, the
+/// escape sequences of the default theme used to take the rest of the 19
+/// columns.
+const COLORED_LEVEL_WIDTH: usize = 10;
+
+/// The level label painted with the theme, padded to a fixed visible width so
+/// the columns line up whatever escape sequences the theme emits.
+pub fn color_by_level(
+    theme: &crate::theme::Theme,
+    labels: &HashMap<String, crate::levels::LevelLabel>,
+    level: &str,
+) -> String {
+    let style = theme.level(level);
+    let text = crate::levels::label_text(labels, level);
+    if let Some(width) = labels.get(level).and_then(|label| label.width) {
+        return format!("{text:<width$}").paint(style).to_string();
     }
+    let width = if yansi::is_enabled() {
+        COLORED_LEVEL_WIDTH
+    } else {
+        LEVEL_WIDTH
+    };
+    let padding = width.saturating_sub(text.chars().count());
+    format!("{}{:padding$}", text.paint(style), "")
 }
 
-pub fn color_by_level(level: &str) -> String {
-    match level {
-        "DEBUG" => format!("{:<19}", "DEBUG".fixed(14).to_string()),
-        "WARNING" => format!("{:<19}", "WARN".yellow().to_string()),
-        "ERROR" => format!("{:<18}", "ERROR".red().to_string()),
-        "INFO" => format!("{:<19}", "INFO".green().to_string()),
-        _ => format!("{:<19}", level.fixed(4).to_string()),
-    }
+/// The number of columns of the terminal stdout is written to.
+#[cfg(unix)]
+pub fn terminal_width() -> Option<usize> {
+    rustix::termios::tcgetwinsize(std::io::stdout())
+        .ok()
+        .map(|size| usize::from(size.ws_col))
+        .filter(|width| *width > 0)
+}
+
+#[cfg(not(unix))]
+pub fn terminal_width() -> Option<usize> {
+    None
 }
 
 pub fn convert_pac_provider_to_fa_icon(provider: &str) -> &str {
@@ -68,6 +89,23 @@ pub fn parse_timestamp_str(value: &str) -> Option<DateTime<Utc>> {
         .map(|timestamp| timestamp.with_timezone(&Utc))
 }
 
+/// Parse a timestamp missing its year (syslog, klog) as UTC, the current year
+/// is assumed unless it would put the timestamp in the future.
+pub fn parse_timestamp_without_year(value: &str, format: &str) -> Option<DateTime<Utc>> {
+    let now = Utc::now();
+    let parse = |year: i32| {
+        NaiveDateTime::parse_from_str(&format!("{year} {value}"), &format!("%Y {format}"))
+            .ok()
+            .map(|naive| naive.and_utc())
+    };
+
+    let timestamp = parse(now.year())?;
+    if timestamp > now + TimeDelta::days(1) {
+        return parse(now.year() - 1);
+    }
+    Some(timestamp)
+}
+
 fn parse_unix_ts(value: &Number) -> Option<DateTime<Utc>> {
     let raw = value.to_string();
     if raw.contains(['e', 'E']) {
@@ -194,6 +232,34 @@ pub fn format_time_delta(delta: TimeDelta) -> String {
     format!("{prefix}{hours}h{minutes:02}m")
 }
 
+/// Format an access log latency as whole milliseconds, e.g. `45ms`.
+pub fn format_latency(duration: Duration) -> String {
+    format!("{}ms", (duration.as_secs_f64() * 1000.0).round() as i64)
+}
+
+/// Parse a human duration like `500ms`, `1.5s`, `2m` or `250us`.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .ok_or_else(|| format!("missing unit in duration '{value}' (e.g. 500ms, 2s)"))?;
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{value}'"))?;
+    let seconds = match unit {
+        "ns" => number / 1_000_000_000.0,
+        "us" | "µs" => number / 1_000_000.0,
+        "ms" => number / 1_000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3_600.0,
+        _ => return Err(format!("unknown unit '{unit}' in duration '{value}'")),
+    };
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|error| format!("invalid duration '{value}': {error}"))
+}
+
 pub fn format_duration_compact(duration: Duration) -> String {
     let total_milliseconds = duration.as_millis();
     if total_milliseconds < 1_000 {
@@ -216,36 +282,51 @@ pub fn format_duration_compact(duration: Duration) -> String {
     format!("{hours}h{minutes:02}m")
 }
 
-/// Applies regex-based styles to a message string using the provided map.
-/// Skips invalid regexes and returns the original string if no match.
-pub fn apply_regexps(regexps: &HashMap<String, Style>, msg: String) -> String {
-    let mut ret = msg;
-    for (key, value) in regexps {
-        let Ok(re) = Regex::new(format!(r"(?P<r>{})", key.as_str()).as_str()) else {
-            continue;
-        };
-        if let Some(matched) = re.find(&ret) {
-            let replace = matched.as_str().paint(*value).to_string();
-            ret = re.replace_all(&ret, replace).to_string();
+/// An ANSI color escape sequence like `\x1b[1;31m`.
+pub(crate) static ANSI_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*m").expect("valid ansi regexp"));
+
+/// Remove the ANSI color escapes from a rendered line.
+pub fn strip_ansi(line: &str) -> String {
+    ANSI_RE.replace_all(line, "").to_string()
+}
+
+/// Match a text against a shell-like glob where `*` matches any run of
+/// characters and `?` a single character, everything else is literal.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => {
+                let Some((star, matched)) = backtrack else {
+                    return false;
+                };
+                p = star + 1;
+                t = matched + 1;
+                backtrack = Some((star, matched + 1));
+            }
         }
     }
-    ret
+
+    pattern[p..].iter().all(|c| *c == '*')
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_level_symbols() {
-        // auto generated :D
-        assert_eq!(level_symbols("DEBUG"), "🐛");
-        assert_eq!(level_symbols("WARNING"), "⚠️");
-        assert_eq!(level_symbols("ERROR"), "🚨");
-        assert_eq!(level_symbols("INFO"), "💡");
-        assert_eq!(level_symbols("FATAL"), "💀");
-        assert_eq!(level_symbols("UNKNOWN"), "∙");
-    }
     #[test]
     fn test_providers() {
         // auto generated :D
@@ -257,6 +338,23 @@ mod tests {
         assert_eq!(convert_pac_provider_to_fa_icon("UNKNOWN"), "UNKNOWN");
     }
 
+    #[test]
+    fn level_labels_have_the_same_visible_width_in_every_theme() {
+        yansi::enable();
+        let labels = HashMap::new();
+        for name in crate::theme::THEMES {
+            let theme = crate::theme::Theme::builtin(name).unwrap();
+            for level in ["DEBUG", "INFO", "WARNING", "ERROR", "FATAL", "CUSTOM"] {
+                let label = color_by_level(&theme, &labels, level);
+                assert_eq!(
+                    strip_ansi(&label).chars().count(),
+                    COLORED_LEVEL_WIDTH,
+                    "{name} {level}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_convert_ts_float_or_str() {
         // auto generated :D
@@ -316,25 +414,46 @@ mod tests {
     }
 
     #[test]
-    fn test_apply_regexps() {
-        let line = String::from("red blue normal");
-        let mut regexps = HashMap::new();
-        regexps.insert(String::from("red"), Style::new().fg(yansi::Color::Red));
-        regexps.insert(
-            String::from(r"\b(b.ue)\b"),
-            Style::new().fg(yansi::Color::Blue),
+    fn test_parse_timestamp_without_year() {
+        let timestamp = parse_timestamp_without_year("Jan  2 03:04:05", "%b %e %H:%M:%S").unwrap();
+        assert_eq!(
+            timestamp.format("%m-%d %H:%M:%S").to_string(),
+            "01-02 03:04:05"
         );
-        let ret = apply_regexps(&regexps, line);
-        assert_eq!(ret, format!("{} {} normal", "red".red(), "blue".blue()));
+        assert!(timestamp <= Utc::now() + TimeDelta::days(1));
+        assert_eq!(parse_timestamp_without_year("nope", "%b %e %H:%M:%S"), None);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1_500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_mins(2)));
+        assert_eq!(parse_duration("250us"), Ok(Duration::from_micros(250)));
+        assert!(parse_duration("500").is_err());
+        assert!(parse_duration("5 parsecs").is_err());
+        assert_eq!(format_latency(Duration::from_micros(1_234)), "1ms");
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        let colored = format!("{} {}", "red".red(), "bold".bold());
+        assert_eq!(strip_ansi(&colored), "red bold");
     }
 
     #[test]
-    fn test_apply_regexps_invalid_regex() {
-        let mut regexps = HashMap::new();
-        regexps.insert(String::from("[invalid"), Style::new().fg(yansi::Color::Red));
-        let msg = String::from("test [invalid regex");
-        // Should not panic, should return original string
-        assert_eq!(apply_regexps(&regexps, msg.clone()), msg);
+    fn test_glob_match() {
+        assert!(glob_match(
+            "*/controller-*[manager]",
+            "ns/controller-abc[manager]"
+        ));
+        assert!(glob_match("ns/pod-?[*]", "ns/pod-1[sidecar]"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match(
+            "*/controller-*[manager]",
+            "ns/controller-abc[proxy]"
+        ));
+        assert!(!glob_match("ns/*", "other/pod[c]"));
     }
 
     #[test]
@@ -118,6 +118,133 @@ snazytest!(
     false
 );
 
+snazytest!(
+    regexp_overlaps_follow_cli_order,
+    [
+        "-r",
+        "red:timeout",
+        "-r",
+        "yellow:timeout after",
+        "-r",
+        "blue:o",
+        "--color",
+        "always"
+    ],
+    "connection timeout after 5s",
+    "c\u{1b}[34mo\u{1b}[0mnnecti\u{1b}[34mo\u{1b}[0mn \u{1b}[31mtimeout\u{1b}[0m after 5s\n",
+    false
+);
+
+snazytest!(
+    highlight_presets_use_the_theme_styles,
+    ["--highlight-preset", "url,duration", "--color", "always"],
+    "GET https://x.io/a took 45ms",
+    "GET \u{1b}[4;34mhttps://x.io/a\u{1b}[0m took \u{1b}[36m45ms\u{1b}[0m\n",
+    false
+);
+
+snazytest!(
+    field_highlights_style_values_and_lines,
+    [
+        "--extra-fields",
+        "--highlight-field",
+        "magenta:namespace=prod",
+        "--highlight-line",
+        "red,bold:status>=500",
+        "--color",
+        "always"
+    ],
+    "{\"level\":\"info\",\"msg\":\"down\",\"status\":503}\n{\"level\":\"info\",\"msg\":\"up\",\"namespace\":\"prod\"}",
+    "\u{1b}[1;31mINFO        down status=503\u{1b}[0m\n\u{1b}[32mINFO\u{1b}[0m        up \u{1b}[1mnamespace\u{1b}[0m=\u{1b}[35mprod\u{1b}[0m\n",
+    false
+);
+
+snazytest!(
+    highlight_patterns_take_flags,
+    [
+        "-r",
+        "foo:bar",
+        "--highlight",
+        "cyan,bold=/error/i",
+        "-r",
+        "=/a.b/lw",
+        "--color",
+        "always"
+    ],
+    "an Error: foo:bar a.b axb",
+    "an \u{1b}[1;36mError\u{1b}[0m: \u{1b}[36mfoo:bar\u{1b}[0m \u{1b}[31ma.b\u{1b}[0m axb\n",
+    false
+);
+
+snazytest!(
+    skip_line_regexp_takes_flags,
+    ["-S", "=/YOLO/iw", "--color", "never"],
+    "{\"level\":\"info\",\"msg\":\"yolo\"}\n{\"level\":\"info\",\"msg\":\"yolooo\"}",
+    "INFO                 yolooo\n",
+    false
+);
+
+snazytest!(
+    skip_line_regexp_keeps_slashes_of_plain_regexps,
+    ["-S", "/healthz/", "--color", "never"],
+    "GET /healthz/ ok\nGET healthz ok",
+    "GET healthz ok\n",
+    false
+);
+
+snazytest!(
+    match_keeps_only_the_matching_lines,
+    ["--match", "=/TIMEOUT/i", "--match", "db", "--match-mode", "all", "--color", "never"],
+    "{\"level\":\"info\",\"msg\":\"db timeout\"}\n{\"level\":\"info\",\"msg\":\"api timeout\"}\nplain db timeout\nplain db",
+    "INFO                 db timeout\nplain db timeout\n",
+    false
+);
+
+snazytest!(
+    klog_lines_are_parsed,
+    ["--extra-fields", "--color", "never"],
+    "I0425 14:20:32.505637 12345 controller.go:59] \"Reconciled\" pod=\"ns/app\" attempt=3",
+    "INFO                14:20:32  controller.go:59 Reconciled attempt=3 pod=ns/app\n",
+    false
+);
+
+snazytest!(
+    raw_lines_levels_are_inferred,
+    ["-f", "error", "--color", "always"],
+    "2024-01-01 12:00:00 ERROR boom\n2024-01-01 12:00:00 INFO fine\nplain text\n2024/01/01 12:00:00 [error] 1#0: upstream timed out",
+    "2024-01-01 12:00:00 \u{1b}[31mERROR\u{1b}[0m boom\n2024/01/01 12:00:00 [\u{1b}[31merror\u{1b}[0m] 1#0: upstream timed out\n",
+    false
+);
+
+snazytest!(
+    plain_raw_lines_do_not_inherit_the_previous_level,
+    ["-f", "error", "--color", "never"],
+    "x ERROR boom\njust a plain line\n  not a continuation either",
+    "x ERROR boom\n",
+    false
+);
+
+snazytest!(
+    raw_lines_inherit_the_previous_level,
+    ["-f", "error", "--color", "never"],
+    "2024-01-01 12:00:00 INFO fine\n2024-01-01 12:00:00 ERROR boom\nTraceback (most recent call last):\n  File \"app.py\", line 1\n2024-01-01 12:00:01 INFO ok\n  continued",
+    "2024-01-01 12:00:00 ERROR boom\nTraceback (most recent call last):\n  File \"app.py\", line 1\n",
+    false
+);
+
+#[test]
+fn invalid_regexps_are_reported_at_startup() {
+    let tenv = testenv::TestEnv::new();
+    for flag in ["-r", "-S", "--action-regexp", "--follow-trigger"] {
+        let output = std::process::Command::new(&tenv.snazy_exe)
+            .args([flag, "=/[oops/i", "/dev/null"])
+            .output()
+            .unwrap();
+        assert!(!output.status.success(), "{flag}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("invalid regexp '=/[oops/i'"));
+    }
+}
+
 snazytest!(
     regexp_color_fg_bg,
     [
@@ -215,6 +342,26 @@ snazytest!(
     false
 );
 
+snazytest!(
+    symbol_set_implies_level_symbols,
+    ["--symbol-set", "ascii"],
+    r#"{"level":"critical","msg":"disk full"}
+    {"level":"trace","msg":"entering"}
+    {"level":"audit","msg":"login"}
+    "#,
+    "[C]  disk full\n[T]  entering\n[?]  login\n",
+    false
+);
+
+snazytest!(
+    symbol_set_covers_extended_levels_of_non_json_lines,
+    ["--color", "never", "--symbol-set", "ascii"],
+    r#"<34>Oct  1 22:14:15 mymachine su[230]: 'su root' failed
+<165>1 2003-10-11T22:14:15.003Z mymachine evntslog - ID47 - An application event"#,
+    "[C] 22:14:15  mymachine su[230] 'su root' failed\n[N] 22:14:15  mymachine evntslog An application event\n",
+    false
+);
+
 snazytest!(
     level_symbols,
     ["--level-symbols"],
@@ -287,7 +434,7 @@ snazytest!(
     stacktrace_hidden,
     ["--color", "never", "--hide-stacktrace"],
     r#"{"level":"error", "ts": "2022-04-25T14:20:32.505637358Z", "msg":"Something went wrong", "stacktrace": "hidden"}"#,
-    "ERROR              14:20:32 Something went wrong\n",
+    "ERROR               14:20:32 Something went wrong\n",
     false
 );
 
@@ -430,7 +577,7 @@ snazytest!(
     zerolog_autodetect,
     ["--color", "never"],
     r#"{"level":"error","message":"zerolog log","time":"2022-04-25T14:20:32.505637358Z"}"#,
-    "ERROR              14:20:32 zerolog log\n",
+    "ERROR               14:20:32 zerolog log\n",
     false
 );
 
@@ -446,7 +593,7 @@ snazytest!(
     cloud_logging_autodetect,
     ["--color", "never"],
     r#"{"severity":"ERROR","textPayload":"cloud log","timestamp":"2022-04-25T14:20:32.505637358Z"}"#,
-    "ERROR              14:20:32 cloud log\n",
+    "ERROR               14:20:32 cloud log\n",
     false
 );
 
@@ -462,7 +609,7 @@ snazytest!(
     zerolog_extra_fields_skip_consumed_stack,
     ["--color", "never", "--extra-fields", "--hide-stacktrace"],
     r#"{"level":"error","message":"zerolog log","time":"2022-04-25T14:20:32.505637358Z","stack":"trace","request_id":"req-1"}"#,
-    "ERROR              14:20:32 zerolog log request_id=req-1\n",
+    "ERROR               14:20:32 zerolog log request_id=req-1\n",
     false
 );
 
@@ -470,7 +617,242 @@ snazytest!(
     cloud_logging_critical_matches_error_filter,
     ["--color", "never", "--filter-levels", "error"],
     r#"{"severity":"CRITICAL","textPayload":"cloud critical","timestamp":"2022-04-25T14:20:32.505637358Z"}"#,
-    "ERROR              14:20:32 cloud critical\n",
+    "CRITICAL            14:20:32 cloud critical\n",
+    false
+);
+
+snazytest!(
+    source_filter_keeps_matching_containers,
+    ["--color", "never", "--source", "*/controller-*[manager]"],
+    r#"pac/controller-abc[manager]: {"level":"info","msg":"kept"}
+pac/controller-abc[proxy]: {"level":"info","msg":"dropped"}
+pac/webhook-abc[manager]: plain dropped"#,
+    "INFO                 pac/controller-abc[manager] kept\n",
+    false
+);
+
+snazytest!(
+    exclude_source_filter_drops_matching_pods,
+    ["--color", "never", "--exclude-source", "kube-system/*"],
+    r#"kube-system/coredns[coredns]: {"level":"info","msg":"dropped"}
+pac/controller[manager]: plain kept
+not a kail line"#,
+    "pac/controller[manager]: plain kept\nnot a kail line\n",
+    false
+);
+
+snazytest!(
+    cri_log_format_unwraps_payload,
+    ["--color", "never"],
+    r#"2024-01-01T00:00:00.1Z stdout P {"level":"info",
+2024-01-01T00:00:00.1Z stdout F "msg":"from containerd"}
+2024-01-01T10:11:12.1Z stderr F plain text on stderr
+2024-01-01T10:11:13.1Z stderr F WARNING: disk almost full"#,
+    "INFO                00:00:00 from containerd\nINFO                10:11:12 plain text on stderr\nWARN                10:11:13 WARNING: disk almost full\n",
+    false
+);
+
+snazytest!(
+    docker_json_file_unwraps_payload,
+    ["--color", "never"],
+    r#"{"log":"{\"level\":\"info\",\"msg\":\"from docker\",\"ts\":\"2022-04-25T14:20:32Z\"}\n","stream":"stdout","time":"2024-01-01T00:00:00Z"}"#,
+    "INFO                14:20:32 from docker\n",
+    false
+);
+
+snazytest!(
+    journald_json_autodetect,
+    ["--color", "never"],
+    r#"{"__REALTIME_TIMESTAMP":"1650896432505637","PRIORITY":"3","_SYSTEMD_UNIT":"kubelet.service","MESSAGE":"failed to sync pod"}"#,
+    "ERROR               14:20:32 kubelet.service failed to sync pod\n",
+    false
+);
+
+snazytest!(
+    journald_export_autodetect,
+    ["--color", "never"],
+    "__CURSOR=s=1\n__REALTIME_TIMESTAMP=1650896432505637\nPRIORITY=6\nSYSLOG_IDENTIFIER=sshd\nMESSAGE=Accepted publickey\n\n",
+    "INFO                14:20:32 sshd Accepted publickey\n",
+    false
+);
+
+snazytest!(
+    syslog_rfc5424_structured_data_fields,
+    ["--color", "never", "--include-fields", "msgid,exampleSDID@32473.iut"],
+    r#"<163>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog 42 ID47 [exampleSDID@32473 iut="3"] disk failure"#,
+    "ERROR               22:14:15  mymachine.example.com evntslog[42] disk failure msgid=ID47 exampleSDID@32473.iut=3\n",
+    false
+);
+
+snazytest!(
+    syslog_rfc3164_autodetect,
+    ["--color", "never", "--filter-levels", "warning"],
+    r#"<12>Oct 11 22:14:15 node1 kubelet[980]: image pull backoff
+<14>Oct 11 22:14:16 node1 kubelet[980]: pod started"#,
+    "WARN                22:14:15  node1 kubelet[980] image pull backoff\n",
+    false
+);
+
+snazytest!(
+    combined_access_log_autodetect,
+    ["--color", "never"],
+    r#"10.0.0.1 - - [25/Apr/2022:14:20:32 +0000] "GET /api/users HTTP/1.1" 500 12 "-" "curl/8.0" rt=0.045 upstream="10.0.0.2:8080""#,
+    "ERROR               14:20:32  45ms 10.0.0.2:8080 GET /api/users -> 500\n",
+    false
+);
+
+snazytest!(
+    traefik_access_log_autodetect,
+    ["--color", "never", "--filter-levels", "warning"],
+    r#"{"RequestMethod":"GET","RequestPath":"/missing","DownstreamStatus":404,"Duration":2000000,"ServiceURL":"http://10.42.0.7","StartUTC":"2022-04-25T14:20:32Z"}
+{"RequestMethod":"GET","RequestPath":"/ok","DownstreamStatus":200,"Duration":2000000,"ServiceURL":"http://10.42.0.7","StartUTC":"2022-04-25T14:20:33Z"}"#,
+    "WARN                14:20:32  2ms http://10.42.0.7 GET /missing -> 404\n",
+    false
+);
+
+snazytest!(
+    serilog_clef_template,
+    ["--color", "never"],
+    r#"{"@t":"2022-04-25T14:20:32Z","@mt":"Order {OrderId} shipped","@l":"Warning","OrderId":1234}"#,
+    "WARN                14:20:32 Order 1234 shipped\n",
+    false
+);
+
+snazytest!(
+    otlp_json_batch,
+    ["--color", "never", "--include-fields", "traceId"],
+    r#"{"resourceLogs":[{"resource":{"attributes":[{"key":"service.name","value":{"stringValue":"checkout"}}]},"scopeLogs":[{"logRecords":[{"timeUnixNano":"1650896432505637000","severityNumber":9,"body":{"stringValue":"cart created"},"traceId":"5b8e"},{"timeUnixNano":"1650896433505637000","severityNumber":13,"body":{"stringValue":"cart expired"},"traceId":"5b8f"}]}]}]}"#,
+    "INFO                14:20:32 checkout cart created traceId=5b8e\nWARN                14:20:33 checkout cart expired traceId=5b8f\n",
+    false
+);
+
+snazytest!(
+    group_by_trace_id,
+    ["--color", "never", "--group-by", "trace_id"],
+    r#"{"level":"info","msg":"a1","time":"2022-04-25T14:20:32Z","trace_id":"a"}
+{"level":"info","msg":"b1","time":"2022-04-25T14:20:33Z","trace_id":"b"}
+{"level":"warning","msg":"a2","time":"2022-04-25T14:20:34Z","trace_id":"a"}"#,
+    "── trace_id=a ── 14:20:32 → 14:20:34 (2s) · 2 lines · WARN\nINFO                14:20:32 a1\nWARN                14:20:34 a2\n── trace_id=b ── 14:20:33 · 1 line · INFO\nINFO                14:20:33 b1\n",
+    false
+);
+
+snazytest!(
+    follow_field_after_first_error,
+    ["--color", "never", "--follow-field", "request_id"],
+    r#"{"level":"info","msg":"start","time":"2022-04-25T14:20:32Z","request_id":"r1"}
+{"level":"error","msg":"failed","time":"2022-04-25T14:20:33Z","request_id":"r1"}
+{"level":"info","msg":"other","time":"2022-04-25T14:20:34Z","request_id":"r2"}
+{"level":"info","msg":"cleanup","time":"2022-04-25T14:20:35Z","request_id":"r1"}"#,
+    "ERROR               14:20:33 failed\nINFO                14:20:35 cleanup\n",
+    false
+);
+
+snazytest!(
+    follow_field_ignores_later_errors,
+    ["--color", "never", "--follow-field", "request_id"],
+    r#"{"level":"error","msg":"failed","time":"2022-04-25T14:20:32Z","request_id":"r1"}
+{"level":"error","msg":"other failure","time":"2022-04-25T14:20:33Z","request_id":"r2"}
+{"level":"info","msg":"other cleanup","time":"2022-04-25T14:20:34Z","request_id":"r2"}
+{"level":"info","msg":"cleanup","time":"2022-04-25T14:20:35Z","request_id":"r1"}"#,
+    "ERROR               14:20:32 failed\nINFO                14:20:35 cleanup\n",
+    false
+);
+
+snazytest!(
+    mark_field_with_value,
+    ["--color", "never", "--mark-field", "request_id=r2"],
+    r#"{"level":"info","msg":"one","time":"2022-04-25T14:20:32Z","request_id":"r1"}
+{"level":"info","msg":"two","time":"2022-04-25T14:20:33Z","request_id":"r2"}"#,
+    "  INFO                14:20:32 one\n▌ INFO                14:20:33 two\n",
+    false
+);
+
+snazytest!(
+    expand_fields_tree,
+    ["--color", "never", "--extra-fields", "--expand-fields"],
+    r#"{"level":"info","msg":"handled","time":"2022-04-25T14:20:32Z","request":{"method":"GET","tags":["a","b"]}}"#,
+    "INFO                14:20:32 handled\n    request:\n      method: GET\n      tags:\n        - a\n        - b\n",
+    false
+);
+
+snazytest!(
+    custom_template_layout,
+    [
+        "--color",
+        "never",
+        "--include-fields",
+        "user",
+        "--template",
+        "{time} {level:5}{? [{field:component}]?} {msg:.12} {extras}"
+    ],
+    r#"{"level":"info","msg":"request handled fine","time":"2022-04-25T14:20:32Z","component":"api","user":"bob"}
+{"level":"warning","msg":"short","time":"2022-04-25T14:20:33Z"}"#,
+    "14:20:32 INFO  [api] request han… user=bob\n14:20:33 WARN  short \n",
+    false
+);
+
+snazytest!(
+    table_output,
+    [
+        "--color",
+        "never",
+        "--output",
+        "table",
+        "--columns",
+        "ts,level,status:6,msg",
+        "--width",
+        "50"
+    ],
+    r#"{"level":"info","msg":"request handled fine for the user","time":"2022-04-25T14:20:32Z","status":200}"#,
+    "TS        LEVEL  STATUS  MSG\n────────  ─────  ──────  ─────────────────────────\n14:20:32  INFO   200     request handled fine for\n                         the user\n",
+    false
+);
+
+snazytest!(
+    csv_output_honors_filters,
+    [
+        "--output",
+        "csv",
+        "--columns",
+        "ts,level,msg,user.name",
+        "--filter-levels",
+        "error",
+        "--time-format",
+        "%H:%M"
+    ],
+    r#"{"level":"info","msg":"skipped","time":"2022-04-25T14:20:32Z"}
+{"level":"error","msg":"failed, retrying","time":"2022-04-25T14:20:33Z","user":{"name":"bob"}}
+plain line"#,
+    "ts,level,msg,user.name\n2022-04-25T14:20:33Z,ERROR,\"failed, retrying\",bob\n",
+    false
+);
+
+snazytest!(
+    csv_output_has_no_summary_or_group_lines,
+    [
+        "--output",
+        "csv",
+        "--collapse-duplicates",
+        "--group-by",
+        "t",
+        "--color",
+        "always"
+    ],
+    r#"{"level":"info","msg":"a","t":"a"}
+{"level":"info","msg":"a","t":"a"}
+{"level":"info","msg":"b","t":"b"}"#,
+    "ts,level,msg\n,INFO,a\n,INFO,b\n",
+    false
+);
+
+snazytest!(
+    slow_requests_only,
+    ["--color", "never", "--slow", "500ms"],
+    r#"{"RequestMethod":"GET","RequestPath":"/slow","DownstreamStatus":200,"Duration":750000000,"StartUTC":"2022-04-25T14:20:32Z"}
+{"RequestMethod":"GET","RequestPath":"/fast","DownstreamStatus":200,"Duration":2000000,"StartUTC":"2022-04-25T14:20:33Z"}
+{"level":"info","msg":"not a request"}
+plain line"#,
+    "INFO                14:20:32  750ms GET /slow -> 200\n",
     false
 );
 
@@ -487,3 +869,113 @@ fn all_json_keys_need_tobe_specified() {
         panic!("{}", testenv::format_exit_error(args, &output));
     }
 }
+
+#[test]
+fn split_output_writes_one_file_per_source() {
+    let tenv = testenv::TestEnv::new();
+    let directory = tempfile::tempdir().unwrap();
+    let mut input = tempfile::NamedTempFile::new().unwrap();
+    input
+        .write_all(
+            br#"ns/api[web]: {"level":"info","msg":"hello"}
+ns/db[pg]: database ready
+"#,
+        )
+        .unwrap();
+    let args = [
+        "--color",
+        "never",
+        "--split-output",
+        directory.path().to_str().unwrap(),
+        input.path().to_str().unwrap(),
+    ];
+    let output = tenv.assert_success_and_get_output(&args);
+
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "INFO                 ns/api[web] hello\nns/db[pg]: database ready\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(directory.path().join("ns_api_web.log")).unwrap(),
+        "INFO                 ns/api[web] hello\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(directory.path().join("ns_db_pg.log")).unwrap(),
+        "ns/db[pg]: database ready\n"
+    );
+}
+
+#[test]
+fn html_output_is_a_standalone_document() {
+    let tenv = testenv::TestEnv::new();
+    let mut input = tempfile::NamedTempFile::new().unwrap();
+    input
+        .write_all(
+            br#"{"level":"error","msg":"boom <here>","stacktrace":"main.go:10"}
+plain & simple
+"#,
+        )
+        .unwrap();
+    let args = [
+        "--color",
+        "always",
+        "--output",
+        "html",
+        input.path().to_str().unwrap(),
+    ];
+    let output = tenv.assert_success_and_get_output(&args);
+    let html = String::from_utf8_lossy(&output.stdout);
+
+    assert!(html.starts_with("<!DOCTYPE html>\n"));
+    assert!(html.ends_with("</body>\n</html>\n"));
+    assert!(html.contains(
+        "<div class=\"line\"><span style=\"color:#cd0000;\">ERROR</span>       boom &lt;here&gt;</div>\n<details><summary>stacktrace</summary>\n"
+    ));
+    assert!(html.contains("<div class=\"line\">plain &amp; simple</div>\n"));
+}
+
+#[test]
+fn themes_come_from_the_config_file() {
+    let tenv = testenv::TestEnv::new();
+    let mut config = tempfile::NamedTempFile::new().unwrap();
+    config
+        .write_all(br#"{"themes": {"paper": {"inherits": "mono", "level.info": "blue"}}}"#)
+        .unwrap();
+    let mut input = tempfile::NamedTempFile::new().unwrap();
+    input
+        .write_all(b"{\"level\":\"info\",\"msg\":\"hello\"}\n")
+        .unwrap();
+    let args = [
+        "--color",
+        "always",
+        "--config",
+        config.path().to_str().unwrap(),
+        "--theme",
+        "paper",
+        input.path().to_str().unwrap(),
+    ];
+    tenv.assert_command_with_output(&args, "\x1b[34mINFO\x1b[0m        hello\n");
+}
+
+#[test]
+fn level_labels_come_from_the_config_file() {
+    let tenv = testenv::TestEnv::new();
+    let mut config = tempfile::NamedTempFile::new().unwrap();
+    config
+        .write_all(br#"{"level_labels": {"notice": {"text": "NOTE", "width": 6}, "warning": {"width": 6}}}"#)
+        .unwrap();
+    let mut input = tempfile::NamedTempFile::new().unwrap();
+    input
+        .write_all(
+            b"{\"level\":\"notice\",\"msg\":\"quota\"}\n{\"level\":\"warning\",\"msg\":\"slow\"}\n",
+        )
+        .unwrap();
+    let args = [
+        "--color",
+        "never",
+        "--config",
+        config.path().to_str().unwrap(),
+        input.path().to_str().unwrap(),
+    ];
+    tenv.assert_command_with_output(&args, "NOTE    quota\nWARN    slow\n");
+}